@@ -1,5 +1,49 @@
-use serde::{Deserialize};
-#[derive( Debug, Deserialize, Clone)]
+use std::{str::FromStr, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::ClientError, gen::TaskWeights, profile::LoadProfile, retry::RetryPolicy, status::ExpectedStatuses, table::TableDistribution};
+
+
+/// HTTP version preference of the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpVersion{
+    /// HTTP/1.1 or HTTP/2 (negotiated by TLS ALPN)
+    Auto,
+
+    /// HTTP/1.1 only
+    Http1,
+
+    /// HTTP/2 without negotiation (prior knowledge)
+    Http2
+}
+
+impl FromStr for HttpVersion{
+    type Err = ClientError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str(){
+            "auto" => Ok(HttpVersion::Auto),
+            "http1" | "http/1.1" | "1" => Ok(HttpVersion::Http1),
+            "http2" | "http/2" | "2" => Ok(HttpVersion::Http2),
+            _ => Err(ClientError::InvalidConfig(format!("unknown HTTP version `{s}` (expected auto, http1 or http2)")))
+        }
+    }
+}
+
+/// Options of HTTP client (connection pool, timeouts and HTTP version). The same options are applied to blocking and async clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientOptions{
+    /// maximum number of idle connections per host (`0` disables keep-alive)
+    pub pool_max_idle_per_host: usize,
+
+    pub pool_idle_timeout: Duration,
+    pub connect_timeout: Duration,
+    pub request_timeout: Duration,
+    pub http_version: HttpVersion
+}
+
+#[derive( Debug, Deserialize, Serialize, Clone)]
 
 /// This struct stores parsed configuration from env
 pub struct Config{
@@ -23,7 +67,71 @@ pub struct Config{
     max_orders_per_request: Option<u8>,
 
     /// Maximum order id for geting specific order and remove order request 
-    max_order_id: Option<i32>
+    max_order_id: Option<i32>,
+
+    /// Acceptable non-2xx HTTP statuses for each task kind (e.g., `GET_ONE=404,REMOVE=404/410`)
+    expected_statuses: Option<String>,
+
+    /// Reuse connections between requests (HTTP keep-alive)
+    keep_alive: Option<bool>,
+
+    /// Maximum number of idle connections kept in the pool for each host
+    pool_max_idle_per_host: Option<usize>,
+
+    /// Time (seconds) an idle connection is kept in the pool
+    pool_idle_timeout_secs: Option<u64>,
+
+    /// Timeout (milliseconds) for establishing connection
+    connect_timeout_ms: Option<u64>,
+
+    /// Timeout (milliseconds) for the whole request (from sending the request until the whole response is received)
+    #[serde(alias = "read_timeout_ms")]
+    request_timeout_ms: Option<u64>,
+
+    /// Time budget (milliseconds) of each task including all attempts and backoffs between them
+    total_timeout_ms: Option<u64>,
+
+    /// Deadline (milliseconds) of the whole load test. Outstanding tasks are cancelled after it
+    load_deadline_ms: Option<u64>,
+
+    /// HTTP version preference (`auto`, `http1` or `http2`)
+    http_version: Option<String>,
+
+    /// Maximum number of in-flight requests of async engine in open-loop mode
+    max_in_flight: Option<usize>,
+
+    /// Stages of duration-based load test (e.g., `10-200:60s,200:5m,200-0:30s`)
+    stages: Option<String>,
+
+    /// Relative weight of each task kind (e.g., `ADD=50,GET_ALL=30,GET_ONE=15,REMOVE=5`)
+    task_weights: Option<String>,
+
+    /// Get one order and remove order tasks target orders created by add orders tasks during the load test
+    stateful_workload: Option<bool>,
+
+    /// Seed of random task generation (the same seed generates the same tasks)
+    seed: Option<u64>,
+
+    /// Distribution of target tables of generated tasks (`uniform`, `zipf[:EXPONENT]`, `hotspot:TABLES:PERCENT` or `round-robin`)
+    table_distribution: Option<String>,
+
+    /// Maximum number of attempts of each request (`1` disables retries)
+    max_attempts: Option<u32>,
+
+    /// Backoff (milliseconds) before the first retry (doubled for each next retry)
+    retry_base_backoff_ms: Option<u64>,
+
+    /// Maximum backoff (milliseconds) between retries
+    retry_max_backoff_ms: Option<u64>,
+
+    /// Fraction (0.0 - 1.0) of the backoff which is randomized
+    retry_jitter: Option<f64>,
+
+    /// Error categories and HTTP statuses which are retried (e.g., `connect,timeout,503`)
+    retry_on: Option<String>,
+
+    /// Retry add orders requests even if the server may have processed them (orders may be duplicated)
+    retry_non_idempotent: Option<bool>
 }
 
 
@@ -35,6 +143,31 @@ impl Config{
         self.max_delay_time_in_secs = None
     }
 
+    /// function to override target endpoint (e.g., for testing with local server)
+    pub fn set_endpoint(&mut self, endpoint: &str){
+        self.endpoint = Some(endpoint.to_string())
+    }
+
+    /// function to override seed of random task generation (e.g., from command line)
+    pub fn set_seed(&mut self, seed: u64){
+        self.seed = Some(seed)
+    }
+
+    /// function to override request timeout (e.g., for testing slow responses)
+    pub fn set_request_timeout(&mut self, timeout: Duration){
+        self.request_timeout_ms = Some(timeout.as_millis() as u64)
+    }
+
+    /// function to override time budget of each task (e.g., for testing retries)
+    pub fn set_total_timeout(&mut self, timeout: Duration){
+        self.total_timeout_ms = Some(timeout.as_millis() as u64)
+    }
+
+    /// function to override deadline of the load test (e.g., from command line)
+    pub fn set_load_deadline(&mut self, deadline: Duration){
+        self.load_deadline_ms = Some(deadline.as_millis() as u64)
+    }
+
     /// utilities functions to get endpoint (if exists in config). Otherwise, it returns default value `http://localhost:3000`
     pub fn get_endpoint(&self) -> String{
         self.endpoint.clone().unwrap_or("http://localhost:3000".to_string())
@@ -69,4 +202,118 @@ impl Config{
     pub fn get_max_order_id(&self) -> i32{
         self.max_order_id.unwrap_or(100)
     }
+
+    /// utilities functions to get acceptable non-2xx statuses (if exists in config). Otherwise, 404 is acceptable for get one order and remove order tasks
+    pub fn get_expected_statuses(&self) -> Result<ExpectedStatuses, ClientError>{
+        self.expected_statuses.as_deref()
+            .map(str::parse)
+            .unwrap_or_else(|| Ok(ExpectedStatuses::default()))
+    }
+
+    /// utilities functions to get keep-alive flag (if exists in config). Otherwise, default value `true` is returned
+    pub fn get_keep_alive(&self) -> bool{
+        self.keep_alive.unwrap_or(true)
+    }
+
+    /// utilities functions to get maximum idle connections per host (if exists in config). Otherwise, max threads is returned
+    pub fn get_pool_max_idle_per_host(&self) -> usize{
+        self.pool_max_idle_per_host.unwrap_or(self.get_max_threads().max(1) as usize)
+    }
+
+    /// utilities functions to get idle connection timeout (if exists in config). Otherwise, default value `90` seconds is returned
+    pub fn get_pool_idle_timeout(&self) -> Duration{
+        Duration::from_secs(self.pool_idle_timeout_secs.unwrap_or(90))
+    }
+
+    /// utilities functions to get connect timeout (if exists in config). Otherwise, default value `5000` ms is returned
+    pub fn get_connect_timeout(&self) -> Duration{
+        Duration::from_millis(self.connect_timeout_ms.unwrap_or(5000))
+    }
+
+    /// utilities functions to get request timeout (if exists in config). Otherwise, default value `30000` ms is returned
+    pub fn get_request_timeout(&self) -> Duration{
+        Duration::from_millis(self.request_timeout_ms.unwrap_or(30000))
+    }
+
+    /// utilities functions to get time budget of each task (if exists in config). Otherwise, `None` is returned (limited by request timeout and retries only)
+    pub fn get_total_timeout(&self) -> Option<Duration>{
+        self.total_timeout_ms.map(Duration::from_millis)
+    }
+
+    /// utilities functions to get deadline of the load test (if exists in config). Otherwise, `None` is returned (no deadline)
+    pub fn get_load_deadline(&self) -> Option<Duration>{
+        self.load_deadline_ms.map(Duration::from_millis)
+    }
+
+    /// utilities functions to get HTTP version preference (if exists in config). Otherwise, `auto` is returned
+    pub fn get_http_version(&self) -> Result<HttpVersion, ClientError>{
+        self.http_version.as_deref()
+            .map(str::parse)
+            .unwrap_or(Ok(HttpVersion::Auto))
+    }
+
+    /// utilities functions to get options of HTTP client. Idle connections are not kept if keep-alive is disabled
+    pub fn get_client_options(&self) -> Result<ClientOptions, ClientError>{
+        Ok(ClientOptions {
+            pool_max_idle_per_host: if self.get_keep_alive() { self.get_pool_max_idle_per_host() } else { 0 },
+            pool_idle_timeout: self.get_pool_idle_timeout(),
+            connect_timeout: self.get_connect_timeout(),
+            request_timeout: self.get_request_timeout(),
+            http_version: self.get_http_version()?
+        })
+    }
+
+    /// utilities functions to get maximum in-flight requests (if exists in config). Otherwise, default value `10000` is returned
+    pub fn get_max_in_flight(&self) -> usize{
+        self.max_in_flight.unwrap_or(10000)
+    }
+
+    /// utilities functions to get load profile (if stages exist in config). Otherwise, `None` is returned (count-based load test)
+    pub fn get_load_profile(&self) -> Result<Option<LoadProfile>, ClientError>{
+        self.stages.as_deref().map(str::parse).transpose()
+    }
+
+    /// utilities functions to get task weights (if exists in config). Otherwise, all task kinds have the same weight
+    pub fn get_task_weights(&self) -> Result<TaskWeights, ClientError>{
+        self.task_weights.as_deref()
+            .map(str::parse)
+            .unwrap_or_else(|| Ok(TaskWeights::default()))
+    }
+
+    /// utilities functions to get stateful workload flag (if exists in config). Otherwise, default value `false` is returned
+    pub fn get_stateful_workload(&self) -> bool{
+        self.stateful_workload.unwrap_or(false)
+    }
+
+    /// utilities functions to get seed (if exists in config). Otherwise, `None` is returned (random seed)
+    pub fn get_seed(&self) -> Option<u64>{
+        self.seed
+    }
+
+    /// utilities functions to get table distribution (if exists in config). Otherwise, `uniform` is returned
+    pub fn get_table_distribution(&self) -> Result<TableDistribution, ClientError>{
+        self.table_distribution.as_deref()
+            .map(str::parse)
+            .unwrap_or(Ok(TableDistribution::Uniform))
+    }
+
+    /// utilities functions to get retry policy (if exists in config) with request timeout and time budget of each task. Otherwise, requests are not retried
+    pub fn get_retry_policy(&self) -> Result<RetryPolicy, ClientError>{
+        let default = RetryPolicy::default();
+        let jitter = self.retry_jitter.unwrap_or(default.jitter);
+        if !(0.0..=1.0).contains(&jitter){
+            return Err(ClientError::InvalidConfig(format!("retry jitter must be between 0.0 and 1.0 (got {jitter})")));
+        }
+
+        Ok(RetryPolicy {
+            max_attempts: self.max_attempts.unwrap_or(default.max_attempts).max(1),
+            base_backoff: self.retry_base_backoff_ms.map(Duration::from_millis).unwrap_or(default.base_backoff),
+            max_backoff: self.retry_max_backoff_ms.map(Duration::from_millis).unwrap_or(default.max_backoff),
+            jitter,
+            retry_on: self.retry_on.as_deref().map(str::parse).transpose()?.unwrap_or(default.retry_on),
+            retry_non_idempotent: self.retry_non_idempotent.unwrap_or(default.retry_non_idempotent),
+            request_timeout: self.get_request_timeout(),
+            total_timeout: self.get_total_timeout()
+        })
+    }
 }
\ No newline at end of file
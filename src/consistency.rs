@@ -0,0 +1,319 @@
+use std::{collections::{BTreeMap, HashMap, HashSet}, fmt, sync::Mutex, time::SystemTime};
+
+use crate::{gen::{TaskInfo, TaskKind}, observer::TaskObserver, result::TaskResult};
+
+
+/// What the client observed from one operation
+#[derive(Debug, Clone, PartialEq)]
+pub enum Observation{
+    /// add orders succeeded and the server assigned these order ids
+    Added(Vec<i32>),
+
+    /// remove order succeeded
+    Removed,
+
+    /// get all orders / get one order succeeded and returned these order ids
+    Read(Vec<i32>),
+
+    /// get one order / remove order returned 404
+    NotFound,
+
+    /// the operation failed (e.g., timeout or 5xx), so it may or may not have taken effect
+    Unknown
+}
+
+/// One operation in the history (invocation and completion of a task)
+#[derive(Debug, Clone)]
+pub struct Operation{
+    pub kind: TaskKind,
+    pub table_id: i16,
+    pub order_id: Option<i32>,
+    pub invoked_at: SystemTime,
+    pub completed_at: SystemTime,
+    pub observation: Observation
+}
+
+impl Operation{
+    /// init function to build operation from the result of the task
+    pub fn from_result(result: &TaskResult) -> Self{
+        let order_ids = || result.table_orders()?.ok().map(|response| response.orders.iter().map(|order| order.order_id).collect());
+
+        let observation = match (result.kind, result.status){
+            (TaskKind::GetOne | TaskKind::Remove, Some(404)) => Some(Observation::NotFound),
+            (TaskKind::Remove, Some(status)) => ((200..300).contains(&status) && result.is_success()).then_some(Observation::Removed),
+            (TaskKind::Add, _) => order_ids().map(Observation::Added),
+            (TaskKind::GetAll | TaskKind::GetOne, _) => order_ids().map(Observation::Read),
+            _ => None
+        }.unwrap_or(Observation::Unknown);
+
+        Self {
+            kind: result.kind,
+            table_id: result.table_id,
+            order_id: result.order_id,
+            invoked_at: result.started_at,
+            completed_at: result.started_at + result.latency,
+            observation
+        }
+    }
+}
+
+
+/// Observer which records history of all operations for the consistency checker
+#[derive(Debug, Default)]
+pub struct HistoryRecorder{
+    operations: Mutex<Vec<Operation>>
+}
+
+impl HistoryRecorder{
+    /// function returns recorded operations
+    pub fn history(&self) -> Vec<Operation>{
+        self.operations.lock().expect("history recorder is poisoned").clone()
+    }
+}
+
+impl TaskObserver for HistoryRecorder{
+    fn on_task_complete(&self, _task: &TaskInfo, result: &TaskResult){
+        self.operations.lock().expect("history recorder is poisoned").push(Operation::from_result(result))
+    }
+}
+
+
+/// Kind of consistency anomaly
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AnomalyKind{
+    /// an order was returned by a read which started after the order had been removed
+    RemovedOrderReappeared,
+
+    /// a read which started after the order had been added did not return it (and no removal of the order could explain it)
+    AddedOrderMissing
+}
+
+impl fmt::Display for AnomalyKind{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self{
+            AnomalyKind::RemovedOrderReappeared => "removed_order_reappeared",
+            AnomalyKind::AddedOrderMissing => "added_order_missing",
+        };
+        f.pad(name)
+    }
+}
+
+/// Anomaly found by the consistency checker
+#[derive(Debug, Clone)]
+pub struct Anomaly{
+    pub kind: AnomalyKind,
+
+    /// kind of the read which observed the anomaly
+    pub read: TaskKind,
+
+    pub table_id: i16,
+    pub order_id: i32
+}
+
+impl fmt::Display for Anomaly{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {} table {} order {}", self.kind, self.read, self.table_id, self.order_id)
+    }
+}
+
+
+/// What is known about one order from the history
+#[derive(Debug, Default)]
+struct OrderHistory{
+    /// completion time of the add orders which created the order
+    added_at: Option<SystemTime>,
+
+    /// completion time of the first succeeded removal
+    removed_at: Option<SystemTime>,
+
+    /// invocation times of removals which may have removed the order (succeeded or unknown outcome)
+    removal_invoked_at: Vec<SystemTime>
+}
+
+impl OrderHistory{
+    /// function checks whether a removal started before `time`, so the order may be missing at `time`
+    fn may_be_removed_before(&self, time: SystemTime) -> bool{
+        self.removal_invoked_at.iter().any(|invoked_at| *invoked_at < time)
+    }
+}
+
+
+/// This function checks whether the history is consistent with a per-table order list where each operation
+/// takes effect at some point between its invocation and completion. Only anomalies which are certain
+/// from real-time order are reported (concurrent operations are not ordered)
+pub fn check_history(history: &[Operation]) -> ConsistencyReport{
+    let mut tables: HashMap<i16, HashMap<i32, OrderHistory>> = HashMap::new();
+    for operation in history{
+        let orders = tables.entry(operation.table_id).or_default();
+        match (&operation.observation, operation.order_id){
+            (Observation::Added(order_ids), _) => {
+                for order_id in order_ids{
+                    let order = orders.entry(*order_id).or_default();
+                    order.added_at = Some(order.added_at.map_or(operation.completed_at, |added_at| added_at.min(operation.completed_at)));
+                }
+            },
+            (Observation::Removed, Some(order_id)) => {
+                let order = orders.entry(order_id).or_default();
+                order.removed_at = Some(order.removed_at.map_or(operation.completed_at, |removed_at| removed_at.min(operation.completed_at)));
+                order.removal_invoked_at.push(operation.invoked_at);
+            },
+            (Observation::Unknown, Some(order_id)) if operation.kind == TaskKind::Remove => {
+                orders.entry(order_id).or_default().removal_invoked_at.push(operation.invoked_at);
+            },
+            _ => {}
+        }
+    }
+
+    let mut anomalies = vec![];
+    for read in history{
+        let Some(orders) = tables.get(&read.table_id) else { continue };
+        let anomaly = |kind: AnomalyKind, order_id: i32| Anomaly { kind, read: read.kind, table_id: read.table_id, order_id };
+
+        let returned: HashSet<i32> = match (&read.observation, read.kind){
+            (Observation::Read(order_ids), _) => order_ids.iter().copied().collect(),
+            (Observation::NotFound, TaskKind::GetOne) => HashSet::new(),
+            _ => continue
+        };
+
+        for order_id in &returned{
+            if orders.get(order_id).and_then(|order| order.removed_at).is_some_and(|removed_at| removed_at < read.invoked_at){
+                anomalies.push(anomaly(AnomalyKind::RemovedOrderReappeared, *order_id));
+            }
+        }
+
+        // get all orders must return every existing order, get one order only the requested one
+        let expected: Vec<(&i32, &OrderHistory)> = match read.kind{
+            TaskKind::GetAll => orders.iter().collect(),
+            _ => read.order_id.and_then(|order_id| orders.get_key_value(&order_id)).into_iter().collect()
+        };
+        for (order_id, order) in expected{
+            let added_before = order.added_at.is_some_and(|added_at| added_at < read.invoked_at);
+            if added_before && !returned.contains(order_id) && !order.may_be_removed_before(read.completed_at){
+                anomalies.push(anomaly(AnomalyKind::AddedOrderMissing, *order_id));
+            }
+        }
+    }
+
+    let mut counts = BTreeMap::new();
+    for anomaly in &anomalies{
+        *counts.entry(anomaly.kind).or_insert(0) += 1;
+    }
+    anomalies.sort_by_key(|anomaly| (anomaly.table_id, anomaly.order_id));
+    anomalies.truncate(REPORTED_ANOMALIES);
+
+    ConsistencyReport { operations: history.len(), anomalies: counts, examples: anomalies }
+}
+
+
+/// number of anomalies shown in the report
+const REPORTED_ANOMALIES: usize = 5;
+
+/// Result of the consistency checker
+#[derive(Debug)]
+pub struct ConsistencyReport{
+    /// number of checked operations
+    pub operations: usize,
+
+    /// number of anomalies for each kind
+    pub anomalies: BTreeMap<AnomalyKind, usize>,
+
+    /// first anomalies (ordered by table and order)
+    pub examples: Vec<Anomaly>
+}
+
+impl fmt::Display for ConsistencyReport{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total: usize = self.anomalies.values().sum();
+        writeln!(f, "Consistency -> OPERATIONS:{} / ANOMALIES: {total}", self.operations)?;
+        if total == 0{
+            return Ok(());
+        }
+
+        let counts: Vec<String> = self.anomalies.iter().map(|(kind, count)| format!("{kind}={count}")).collect();
+        writeln!(f, "Anomalies -> {}", counts.join(" "))?;
+        for anomaly in &self.examples{
+            writeln!(f, "  {anomaly}")?;
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod test{
+    use std::time::Duration;
+
+    use crate::test_support;
+
+    use super::*;
+
+    /// operation between `invoked` and `completed` (milliseconds from now)
+    fn operation(kind: TaskKind, order_id: Option<i32>, invoked: u64, completed: u64, observation: Observation) -> Operation{
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        Operation {
+            kind,
+            table_id: 1,
+            order_id,
+            invoked_at: start + Duration::from_millis(invoked),
+            completed_at: start + Duration::from_millis(completed),
+            observation
+        }
+    }
+
+    #[test]
+    fn test_operation_from_result(){
+        let observation = |kind: TaskKind, status: u16, body: &str| {
+            let mut result = test_support::result(kind, status);
+            result.outcome = Ok(body.to_string());
+            Operation::from_result(&result).observation
+        };
+        let body = r#"{"table_id":1,"orders":[{"order_id":3,"table_id":1,"item_name":"item-1","note":null}]}"#;
+
+        assert_eq!(observation(TaskKind::Add, 201, body), Observation::Added(vec![3]));
+        assert_eq!(observation(TaskKind::GetOne, 200, body), Observation::Read(vec![3]));
+        assert_eq!(observation(TaskKind::GetOne, 404, "not found"), Observation::NotFound);
+        assert_eq!(observation(TaskKind::Remove, 200, ""), Observation::Removed);
+        // order id out of range of i32 is not truncated
+        assert_eq!(observation(TaskKind::GetAll, 200, &body.replace("\"order_id\":3", "\"order_id\":4294967299")), Observation::Unknown);
+        assert_eq!(observation(TaskKind::GetAll, 500, body), Observation::Unknown);
+    }
+
+    #[test]
+    fn test_consistent_history(){
+        let history = vec![
+            operation(TaskKind::Add, None, 0, 10, Observation::Added(vec![1, 2])),
+            operation(TaskKind::GetAll, None, 20, 30, Observation::Read(vec![1, 2])),
+            operation(TaskKind::Remove, Some(1), 40, 50, Observation::Removed),
+            // concurrent with the removal, so both results are fine
+            operation(TaskKind::GetAll, None, 45, 55, Observation::Read(vec![1, 2])),
+            operation(TaskKind::GetOne, Some(1), 45, 55, Observation::NotFound),
+            operation(TaskKind::GetAll, None, 60, 70, Observation::Read(vec![2])),
+            // the removal may have happened
+            operation(TaskKind::Remove, Some(2), 80, 90, Observation::Unknown),
+            operation(TaskKind::GetAll, None, 100, 110, Observation::Read(vec![])),
+        ];
+
+        let report = check_history(&history);
+        assert_eq!(report.operations, 8);
+        assert!(report.anomalies.is_empty(), "{report}");
+    }
+
+    #[test]
+    fn test_anomalies(){
+        let history = vec![
+            operation(TaskKind::Add, None, 0, 10, Observation::Added(vec![1, 2])),
+            operation(TaskKind::Remove, Some(1), 20, 30, Observation::Removed),
+            // order 1 is back, order 2 is lost
+            operation(TaskKind::GetAll, None, 40, 50, Observation::Read(vec![1])),
+            operation(TaskKind::GetOne, Some(1), 40, 50, Observation::Read(vec![1])),
+            operation(TaskKind::GetOne, Some(2), 40, 50, Observation::NotFound),
+        ];
+
+        let report = check_history(&history);
+        assert_eq!(report.anomalies, BTreeMap::from([
+            (AnomalyKind::RemovedOrderReappeared, 2),
+            (AnomalyKind::AddedOrderMissing, 2)
+        ]));
+        assert!(report.to_string().contains("ANOMALIES: 4"));
+    }
+}
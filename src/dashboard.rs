@@ -0,0 +1,200 @@
+use std::{collections::{BTreeMap, VecDeque}, fmt::Write as _, io::{self, Write}, sync::{atomic::{AtomicI64, Ordering}, mpsc::{self, RecvTimeoutError, Sender}, Arc, Mutex}, thread::{self, JoinHandle}, time::{Duration, Instant}};
+
+use crate::{error::ErrorCategory, gen::{TaskInfo, TaskKind}, observer::TaskObserver, report::percentile, result::TaskResult};
+
+
+/// refresh interval of the dashboard
+const REFRESH: Duration = Duration::from_secs(1);
+
+/// window of rolling latency percentiles
+const LATENCY_WINDOW: Duration = Duration::from_secs(10);
+
+/// number of throughput samples (one per refresh) in the sparkline
+const SPARKLINE_LEN: usize = 60;
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// ANSI escape codes: clear screen and move cursor home / hide and show cursor
+const CLEAR: &str = "\x1b[2J\x1b[H";
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+
+
+#[derive(Debug, Default)]
+struct DashboardState{
+    /// completion time, kind and latency of requests in the latency window
+    recent: VecDeque<(Instant, TaskKind, Duration)>,
+
+    completed: u64,
+    failed: u64,
+    errors: BTreeMap<ErrorCategory, u64>,
+
+    /// completed requests / time at the previous refresh
+    last_tick: Option<(u64, Instant)>,
+
+    /// requests per second at each refresh (the latest last)
+    throughput: VecDeque<f64>
+}
+
+/// Observer which collects live statistics of the load test for the terminal dashboard
+#[derive(Debug)]
+pub struct Dashboard{
+    start: Instant,
+    in_flight: AtomicI64,
+    state: Mutex<DashboardState>
+}
+
+impl Default for Dashboard{
+    fn default() -> Self {
+        Self { start: Instant::now(), in_flight: AtomicI64::new(0), state: Mutex::default() }
+    }
+}
+
+impl Dashboard{
+    /// function takes throughput sample at `now` and renders the dashboard (without ANSI escape codes)
+    pub fn tick(&self, now: Instant) -> String{
+        let mut state = self.state.lock().expect("dashboard is poisoned");
+        while state.recent.front().is_some_and(|(completed_at, _, _)| now.duration_since(*completed_at) > LATENCY_WINDOW){
+            state.recent.pop_front();
+        }
+
+        let (last_completed, last_time) = state.last_tick.unwrap_or((0, self.start));
+        let elapsed = now.duration_since(last_time).as_secs_f64();
+        let rps = if elapsed > 0.0 { (state.completed - last_completed) as f64 / elapsed } else { 0.0 };
+        state.last_tick = Some((state.completed, now));
+        state.throughput.push_back(rps);
+        if state.throughput.len() > SPARKLINE_LEN{
+            state.throughput.pop_front();
+        }
+
+        let mut out = String::new();
+        let elapsed = now.duration_since(self.start).as_secs();
+        let _ = writeln!(out, "Load test -> elapsed {:02}:{:02}  completed {}  failed {}", elapsed / 60, elapsed % 60, state.completed, state.failed);
+        let _ = writeln!(out, "RPS {rps:>8.1}   in-flight {}", self.in_flight.load(Ordering::Relaxed));
+        let max = state.throughput.iter().copied().fold(0.0, f64::max);
+        let _ = writeln!(out, "Throughput {} (max {max:.1}/s)", sparkline(&state.throughput));
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "{:<8} {:>8} {:>10} {:>10}   (last {} s)", "kind", "count", "p50(ms)", "p99(ms)", LATENCY_WINDOW.as_secs());
+        for kind in TaskKind::ALL{
+            let mut latencies: Vec<Duration> = state.recent.iter().filter(|(_, x, _)| *x == kind).map(|(_, _, latency)| *latency).collect();
+            if latencies.is_empty(){
+                let _ = writeln!(out, "{:<8} {:>8} {:>10} {:>10}", kind.to_string(), 0, "-", "-");
+                continue;
+            }
+            latencies.sort_unstable();
+            let millis = |p: f64| percentile(&latencies, p).as_secs_f64() * 1000.0;
+            let _ = writeln!(out, "{:<8} {:>8} {:>10.3} {:>10.3}", kind.to_string(), latencies.len(), millis(50.0), millis(99.0));
+        }
+
+        let _ = writeln!(out);
+        let errors: Vec<String> = state.errors.iter().map(|(category, count)| format!("{category}={count}")).collect();
+        let _ = writeln!(out, "Errors -> {}", if errors.is_empty() { "none".to_string() } else { errors.join(" ") });
+        out
+    }
+}
+
+/// function renders samples as a sparkline scaled to the maximum sample
+fn sparkline(samples: &VecDeque<f64>) -> String{
+    let max = samples.iter().copied().fold(0.0, f64::max);
+    samples.iter()
+        .map(|sample| if max > 0.0 { SPARKS[((sample / max) * (SPARKS.len() - 1) as f64).round() as usize] } else { SPARKS[0] })
+        .collect()
+}
+
+impl TaskObserver for Dashboard{
+    fn on_task_start(&self, _task: &TaskInfo){
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_task_complete(&self, _task: &TaskInfo, result: &TaskResult){
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+
+        let mut state = self.state.lock().expect("dashboard is poisoned");
+        state.recent.push_back((Instant::now(), result.kind, result.latency));
+        state.completed += 1;
+        if let Some(category) = result.error_category(){
+            state.failed += 1;
+            *state.errors.entry(category).or_insert(0) += 1;
+        }
+    }
+}
+
+
+/// Thread which redraws the dashboard on the terminal every second until it is finished
+pub struct DashboardRenderer{
+    stop: Sender<()>,
+    thread: JoinHandle<()>
+}
+
+impl DashboardRenderer{
+    /// function starts redrawing the dashboard on stdout
+    pub fn start(dashboard: Arc<Dashboard>) -> io::Result<Self>{
+        let (stop, stopped) = mpsc::channel();
+        let thread = thread::Builder::new().name("dashboard".to_string()).spawn(move || {
+            let draw = |frame: String| {
+                let mut stdout = io::stdout().lock();
+                let _ = write!(stdout, "{CLEAR}{frame}");
+                let _ = stdout.flush();
+            };
+
+            print!("{HIDE_CURSOR}");
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(REFRESH){
+                draw(dashboard.tick(Instant::now()));
+            }
+            // the last frame is kept on the screen above the final report
+            draw(dashboard.tick(Instant::now()));
+            println!("{SHOW_CURSOR}");
+        })?;
+        Ok(Self { stop, thread })
+    }
+
+    /// function stops redrawing after the last frame
+    pub fn finish(self){
+        let _ = self.stop.send(());
+        let _ = self.thread.join();
+    }
+}
+
+
+#[cfg(test)]
+mod test{
+    use std::time::SystemTime;
+
+    use crate::{error::ClientError, test_support};
+
+    use super::*;
+
+    #[test]
+    fn test_sparkline(){
+        assert_eq!(sparkline(&VecDeque::from([0.0, 50.0, 100.0])), "▁▅█");
+        assert_eq!(sparkline(&VecDeque::from([0.0, 0.0])), "▁▁");
+    }
+
+    #[test]
+    fn test_dashboard_frame(){
+        let task = test_support::task(TaskKind::GetAll);
+        let dashboard = Dashboard::default();
+
+        dashboard.on_task_start(&task);
+        for latency in [10, 20, 30]{
+            dashboard.on_task_start(&task);
+            let mut result = test_support::result(TaskKind::GetAll, 200);
+            result.latency = Duration::from_millis(latency);
+            dashboard.on_task_complete(&task, &result);
+        }
+        dashboard.on_task_complete(&task, &TaskResult::new(&task, SystemTime::now(), Duration::from_millis(5), None, Err(ClientError::DeadlineExceeded)));
+
+        let frame = dashboard.tick(dashboard.start + Duration::from_secs(2));
+        assert!(frame.contains("completed 4  failed 1"), "{frame}");
+        assert!(frame.contains("RPS      2.0   in-flight 0"), "{frame}");
+        assert!(frame.contains("GetAll          4     10.000     30.000"), "{frame}");
+        assert!(frame.contains("Errors -> timeout=1"), "{frame}");
+
+        // requests are dropped from the latency window, the throughput is sampled from the previous refresh
+        let frame = dashboard.tick(dashboard.start + Duration::from_secs(60));
+        assert!(frame.contains("GetAll          0          -          -"), "{frame}");
+        assert!(frame.contains("RPS      0.0"), "{frame}");
+        assert!(frame.contains("Throughput █▁ (max 2.0/s)"), "{frame}");
+    }
+}
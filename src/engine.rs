@@ -0,0 +1,317 @@
+use std::{fmt, str::FromStr, sync::{Arc, Mutex}, time::{Duration, Instant, SystemTime}};
+
+use reqwest::{header::CONTENT_TYPE, Client, RequestBuilder};
+use tokio::{sync::Semaphore, task::JoinSet};
+
+use crate::{config::{Config, HttpVersion}, error::ClientError, gen::TaskInfo, observer::TaskObserver, profile::LoadProfile, request::task_request, result::TaskResult, retry::{self, RetryPolicy}, status::ExpectedStatuses};
+
+
+/// Engine which executes load test tasks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadEngine{
+    /// blocking requests executed by rayon workers (closed-loop, one request per worker)
+    Blocking,
+
+    /// async requests executed on tokio (closed-loop, or open-loop with target rate)
+    Async
+}
+
+impl FromStr for LoadEngine{
+    type Err = ClientError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str(){
+            "blocking" => Ok(LoadEngine::Blocking),
+            "async" => Ok(LoadEngine::Async),
+            _ => Err(ClientError::InvalidConfig(format!("unknown load engine `{s}` (expected blocking or async)")))
+        }
+    }
+}
+
+impl fmt::Display for LoadEngine{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self{
+            LoadEngine::Blocking => "blocking",
+            LoadEngine::Async => "async",
+        };
+        f.pad(name)
+    }
+}
+
+
+/// This function builds async HTTP client from config (same options as `request::build_client`)
+pub fn build_async_client(config: &Config) -> Result<Client, ClientError>{
+    let options = config.get_client_options()?;
+    let builder = Client::builder()
+            .pool_max_idle_per_host(options.pool_max_idle_per_host)
+            .pool_idle_timeout(options.pool_idle_timeout)
+            .connect_timeout(options.connect_timeout)
+            .timeout(options.request_timeout);
+
+    let builder = match options.http_version{
+        HttpVersion::Auto => builder,
+        HttpVersion::Http1 => builder.http1_only(),
+        HttpVersion::Http2 => builder.http2_prior_knowledge(),
+    };
+    builder.build().map_err(|err| ClientError::BuilderError(Box::new(err)))
+}
+
+
+/// function converts task into async HTTP request (same as `request::build_request`)
+fn prepare_request(client: &Client, task: &TaskInfo) -> Result<RequestBuilder, ClientError>{
+    let (method, url, body) = task_request(task)?;
+    let request = client.request(method, url);
+    Ok(match body{
+        Some(body) => request.header(CONTENT_TYPE, "application/json").body(body),
+        None => request
+    })
+}
+
+
+/// This function executes a single task asynchronously and checks the response status. Failed requests are retried according to the retry policy.
+/// The latency is measured from `intended_start` (when the request should have been sent), so time waiting for a free slot and retries are included.
+/// Each attempt is limited by the client request timeout. The task is cancelled at `deadline` of the load test or when the total timeout
+/// of the task runs out (reported as timed out)
+pub async fn execute_task_async(client: &Client, task: &TaskInfo, expected: &ExpectedStatuses, retry: &RetryPolicy, deadline: Option<Instant>, intended_start: Instant, observer: &dyn TaskObserver) -> TaskResult{
+    observer.on_task_start(task);
+    let deadline = retry.task_deadline(Instant::now(), deadline);
+
+    let attempts = async {
+        let mut attempt = 1;
+        let mut result = loop{
+            let result = send_request_async(client, task, intended_start).await.check_status(expected);
+            if !retry.should_retry(task.kind, &result, attempt){
+                break result;
+            }
+            let backoff = retry.backoff(attempt, result.retry_after, &mut rand::thread_rng());
+            if deadline.is_some_and(|deadline| Instant::now() + backoff >= deadline){
+                break result;
+            }
+            tokio::time::sleep(backoff).await;
+            attempt += 1;
+        };
+        result.attempts = attempt;
+        result
+    };
+
+    let result = match deadline{
+        Some(deadline) => tokio::time::timeout_at(deadline.into(), attempts).await.unwrap_or_else(|_| {
+            let started_at = SystemTime::now() - intended_start.elapsed();
+            TaskResult::new(task, started_at, intended_start.elapsed(), None, Err(ClientError::DeadlineExceeded))
+        }),
+        None => attempts.await
+    };
+    observer.on_task_complete(task, &result);
+    result
+}
+
+async fn send_request_async(client: &Client, task: &TaskInfo, intended_start: Instant) -> TaskResult{
+    let started_at = SystemTime::now() - intended_start.elapsed();
+    tracing::info!("[request {}] {}", task.kind, task.endpoint_url);
+
+    let request = match prepare_request(client, task){
+        Ok(request) => request,
+        Err(err) => return TaskResult::new(task, started_at, intended_start.elapsed(), None, Err(err))
+    };
+
+    let response = request.send().await;
+    let status = response.as_ref().ok().map(|res| res.status().as_u16());
+    let retry_after = response.as_ref().ok().and_then(|res| retry::retry_after(res.headers()));
+    let outcome = match response{
+        Ok(response) => response.text().await.map_err(ClientError::from),
+        Err(err) => Err(ClientError::from(err))
+    };
+
+    let mut result = TaskResult::new(task, started_at, intended_start.elapsed(), status, outcome);
+    result.retry_after = retry_after;
+    result
+}
+
+
+/// Closed-loop load: `concurrency` workers execute tasks one after another (and sleep for the task's delay time after each task).
+/// No task is started after `deadline`
+pub async fn run_closed_loop(client: Client, tasks: impl Iterator<Item = TaskInfo> + Send + 'static, expected: ExpectedStatuses, retry: RetryPolicy, deadline: Option<Instant>, concurrency: usize, observer: Arc<dyn TaskObserver>) -> Vec<TaskResult>{
+    let queue = Arc::new(Mutex::new(tasks));
+    let expected = Arc::new(expected);
+    let retry = Arc::new(retry);
+
+    let mut workers = JoinSet::new();
+    for _ in 0..concurrency.max(1){
+        let (client, queue, expected, retry, observer) = (client.clone(), queue.clone(), expected.clone(), retry.clone(), observer.clone());
+
+        workers.spawn(async move {
+            let mut results = vec![];
+            loop{
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline){
+                    break;
+                }
+                let next = queue.lock().expect("task queue is poisoned").next();
+                let Some(task) = next else { break };
+
+                results.push(execute_task_async(&client, &task, &expected, &retry, deadline, Instant::now(), observer.as_ref()).await);
+                let delay = tokio::time::sleep(Duration::from_secs(task.delay_time));
+                match deadline{
+                    Some(deadline) => { let _ = tokio::time::timeout_at(deadline.into(), delay).await; },
+                    None => delay.await
+                }
+            }
+            results
+        });
+    }
+
+    let mut results = vec![];
+    while let Some(worker) = workers.join_next().await{
+        results.extend(worker.expect("load worker panicked"));
+    }
+    results
+}
+
+
+/// function checks request rate of open-loop load
+pub fn check_rate(rate: f64) -> Result<(), ClientError>{
+    if !(rate.is_finite() && rate > 0.0){
+        return Err(ClientError::InvalidConfig(format!("request rate must be positive (got {rate})")));
+    }
+    Ok(())
+}
+
+/// Open-loop load: tasks are started at `rate` requests/sec regardless of response time (delay time of tasks is ignored).
+/// At most `max_in_flight` requests are outstanding and no task is started after `deadline`
+#[allow(clippy::too_many_arguments)]
+pub async fn run_open_loop(client: Client, tasks: impl Iterator<Item = TaskInfo>, expected: ExpectedStatuses, retry: RetryPolicy, deadline: Option<Instant>, rate: f64, max_in_flight: usize, observer: Arc<dyn TaskObserver>) -> Result<Vec<TaskResult>, ClientError>{
+    check_rate(rate)?;
+
+    let interval = Duration::from_secs_f64(1.0 / rate);
+    let schedule = tasks.enumerate().map(|(index, task)| (interval.mul_f64(index as f64), task));
+    Ok(run_scheduled(client, schedule, expected, retry, deadline, max_in_flight, observer).await)
+}
+
+
+/// Duration-based open-loop load: request rate follows the stages of the profile and tasks are taken lazily from `tasks` until the profile ends (or `deadline`)
+#[allow(clippy::too_many_arguments)]
+pub async fn run_profile(client: Client, profile: &LoadProfile, tasks: impl Iterator<Item = TaskInfo>, expected: ExpectedStatuses, retry: RetryPolicy, deadline: Option<Instant>, max_in_flight: usize, observer: Arc<dyn TaskObserver>) -> Vec<TaskResult>{
+    run_scheduled(client, profile.arrivals().zip(tasks), expected, retry, deadline, max_in_flight, observer).await
+}
+
+
+/// This function starts each task at its scheduled time (offset from now), e.g. to replay recorded workload. At most `max_in_flight` requests are outstanding.
+/// Requests which cannot start on time still measure latency from their scheduled time. Tasks scheduled after `deadline` are not started
+pub async fn run_scheduled(client: Client, schedule: impl Iterator<Item = (Duration, TaskInfo)>, expected: ExpectedStatuses, retry: RetryPolicy, deadline: Option<Instant>, max_in_flight: usize, observer: Arc<dyn TaskObserver>) -> Vec<TaskResult>{
+    let slots = Arc::new(Semaphore::new(max_in_flight.max(1)));
+    let expected = Arc::new(expected);
+    let retry = Arc::new(retry);
+    let start = Instant::now();
+
+    let mut in_flight = JoinSet::new();
+    for (offset, task) in schedule{
+        let intended_start = start + offset;
+        if deadline.is_some_and(|deadline| intended_start >= deadline){
+            break;
+        }
+        tokio::time::sleep_until(intended_start.into()).await;
+
+        let slot = slots.clone().acquire_owned().await.expect("in-flight limit is closed");
+        let (client, expected, retry, observer) = (client.clone(), expected.clone(), retry.clone(), observer.clone());
+        in_flight.spawn(async move {
+            let result = execute_task_async(&client, &task, &expected, &retry, deadline, intended_start, observer.as_ref()).await;
+            drop(slot);
+            result
+        });
+    }
+
+    let mut results = vec![];
+    while let Some(result) = in_flight.join_next().await{
+        results.push(result.expect("load task panicked"));
+    }
+    results
+}
+
+
+#[cfg(test)]
+mod test{
+    use crate::{mock::{MockOptions, MockServer}, observer::Observers, test_support};
+
+    use super::*;
+
+    fn unreachable_config() -> Config{
+        let mut config = test_support::config();
+        // nothing should listen on port 1
+        config.set_endpoint("http://127.0.0.1:1");
+        config
+    }
+
+    #[test]
+    fn test_parse_load_engine(){
+        assert_eq!("async".parse::<LoadEngine>().unwrap(), LoadEngine::Async);
+        assert_eq!("Blocking".parse::<LoadEngine>().unwrap(), LoadEngine::Blocking);
+        assert!("threads".parse::<LoadEngine>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_closed_loop_executes_all_tasks(){
+        let config = unreachable_config();
+        let client = build_async_client(&config).unwrap();
+        let mut rng = test_support::rng();
+        let tasks: Vec<TaskInfo> = (1..=10).map(|table_id| TaskInfo::new_get_all_task(table_id, &config, &mut rng)).collect();
+
+        let results = run_closed_loop(client, tasks.into_iter(), ExpectedStatuses::default(), RetryPolicy::none(), None, 4, Arc::new(Observers::default())).await;
+        assert_eq!(results.len(), 10);
+        assert!(results.iter().all(|result| result.error_category() == Some(crate::error::ErrorCategory::Connect)));
+    }
+
+    #[tokio::test]
+    async fn test_open_loop_keeps_rate(){
+        let config = unreachable_config();
+        let client = build_async_client(&config).unwrap();
+        let mut rng = test_support::rng();
+        let tasks = (1..=20).map(|table_id| TaskInfo::new_get_all_task(table_id, &config, &mut rng));
+
+        let start = Instant::now();
+        let results = run_open_loop(client, tasks, ExpectedStatuses::default(), RetryPolicy::none(), None, 100.0, 100, Arc::new(Observers::default())).await.unwrap();
+
+        assert_eq!(results.len(), 20);
+        // the last task is scheduled at 190 ms
+        assert!(start.elapsed() >= Duration::from_millis(190));
+    }
+
+    #[tokio::test]
+    async fn test_profile_runs_for_its_duration(){
+        let config = unreachable_config();
+        let client = build_async_client(&config).unwrap();
+        let mut rng = test_support::rng();
+        let profile: LoadProfile = "50:200ms,100:100ms".parse().unwrap();
+        let tasks = (1..).map(|table_id| TaskInfo::new_get_all_task(table_id, &config, &mut rng));
+
+        let start = Instant::now();
+        let results = run_profile(client, &profile, tasks, ExpectedStatuses::default(), RetryPolicy::none(), None, 100, Arc::new(Observers::default())).await;
+
+        assert_eq!(results.len(), 20);
+        assert!(start.elapsed() >= Duration::from_millis(290));
+    }
+
+    #[tokio::test]
+    async fn test_deadline_cancels_outstanding_tasks(){
+        let server = MockServer::start(MockOptions { latency: Duration::from_secs(5), ..Default::default() }).unwrap();
+        let mut config = unreachable_config();
+        config.set_endpoint(&server.endpoint());
+        let client = build_async_client(&config).unwrap();
+        let mut rng = test_support::rng();
+        let tasks: Vec<TaskInfo> = (1..=10).map(|table_id| TaskInfo::new_get_all_task(table_id, &config, &mut rng)).collect();
+
+        let start = Instant::now();
+        let deadline = Some(start + Duration::from_millis(200));
+        let results = run_closed_loop(client, tasks.into_iter(), ExpectedStatuses::default(), RetryPolicy::none(), deadline, 4, Arc::new(Observers::default())).await;
+
+        // only the first task of each worker was started, and it timed out at the deadline
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert_eq!(results.len(), 4);
+        assert!(results.iter().all(|result| matches!(result.outcome, Err(ClientError::DeadlineExceeded))));
+    }
+
+    #[tokio::test]
+    async fn test_open_loop_invalid_rate(){
+        let config = unreachable_config();
+        let client = build_async_client(&config).unwrap();
+        assert!(run_open_loop(client, std::iter::empty(), ExpectedStatuses::default(), RetryPolicy::none(), None, 0.0, 1, Arc::new(Observers::default())).await.is_err());
+    }
+}
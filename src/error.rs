@@ -1,10 +1,274 @@
+use std::{error::Error as StdError, fmt, str::FromStr};
+
 use thiserror::Error;
 
+/// Boxed error type to keep the source of errors which are not from reqwest
+pub type BoxError = Box<dyn StdError + Send + Sync>;
+
+/// Maximum number of characters of the response body kept in HTTP errors
+const BODY_EXCERPT_LEN: usize = 200;
+
 #[derive(Error, Debug)]
 pub enum ClientError{
-    #[error("Json request serialization error")]
-    SerializationError,
-    
-    #[error("Request communication error")]
-    RequestError
-}
\ No newline at end of file
+    #[error("Json request serialization error: {0}")]
+    SerializationError(#[source] BoxError),
+
+    #[error("Cannot connect to server: {0}")]
+    ConnectError(#[source] reqwest::Error),
+
+    #[error("Request timed out: {0}")]
+    TimeoutError(#[source] reqwest::Error),
+
+    #[error("Request cancelled at the load test deadline or after the total timeout of the task")]
+    DeadlineExceeded,
+
+    #[error("Cannot resolve server address: {0}")]
+    DnsError(#[source] reqwest::Error),
+
+    #[error("TLS error: {0}")]
+    TlsError(#[source] reqwest::Error),
+
+    #[error("HTTP client error (status {status}): {body}")]
+    HttpClientError{ status: u16, body: String },
+
+    #[error("HTTP server error (status {status}): {body}")]
+    HttpServerError{ status: u16, body: String },
+
+    /// non-2xx status which is neither client nor server error (e.g., 3xx not followed by the client)
+    #[error("Unexpected HTTP status {status}: {body}")]
+    HttpUnexpectedStatus{ status: u16, body: String },
+
+    #[error("Cannot decode response body: {0}")]
+    DecodeError(#[source] reqwest::Error),
+
+    /// invalid option or parameter detected by this crate
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+
+    /// HTTP client or request cannot be built (e.g., invalid endpoint url or TLS backend failure)
+    #[error("Cannot build HTTP client or request: {0}")]
+    BuilderError(#[source] BoxError),
+
+    #[error("Request communication error: {0}")]
+    RequestError(#[source] reqwest::Error),
+
+    #[error("File error: {0}")]
+    IoError(#[from] std::io::Error)
+}
+
+/// Coarse category of `ClientError`. Used for counting errors in the report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ErrorCategory{
+    Serialization,
+    Connect,
+    Timeout,
+    Dns,
+    Tls,
+    Http4xx,
+    Http5xx,
+    HttpOther,
+    Decode,
+    InvalidConfig,
+    Request,
+    Io
+}
+
+impl ClientError{
+    /// function returns category of the error
+    pub fn category(&self) -> ErrorCategory{
+        match self{
+            ClientError::SerializationError(_) => ErrorCategory::Serialization,
+            ClientError::ConnectError(_) => ErrorCategory::Connect,
+            ClientError::TimeoutError(_) | ClientError::DeadlineExceeded => ErrorCategory::Timeout,
+            ClientError::DnsError(_) => ErrorCategory::Dns,
+            ClientError::TlsError(_) => ErrorCategory::Tls,
+            ClientError::HttpClientError { .. } => ErrorCategory::Http4xx,
+            ClientError::HttpServerError { .. } => ErrorCategory::Http5xx,
+            ClientError::HttpUnexpectedStatus { .. } => ErrorCategory::HttpOther,
+            ClientError::DecodeError(_) => ErrorCategory::Decode,
+            ClientError::InvalidConfig(_) | ClientError::BuilderError(_) => ErrorCategory::InvalidConfig,
+            ClientError::RequestError(_) => ErrorCategory::Request,
+            ClientError::IoError(_) => ErrorCategory::Io,
+        }
+    }
+
+    /// init function to create HTTP error from status code and response body. Only an excerpt of the body is kept
+    pub fn from_status(status: u16, body: &str) -> Self{
+        let body: String = body.chars().take(BODY_EXCERPT_LEN).collect();
+        match status{
+            400..=499 => ClientError::HttpClientError { status, body },
+            500..=599 => ClientError::HttpServerError { status, body },
+            _ => ClientError::HttpUnexpectedStatus { status, body }
+        }
+    }
+}
+
+/// Cause of a connect error
+#[derive(Debug, PartialEq, Eq)]
+enum ConnectFailure{
+    Dns,
+    Tls,
+    Other
+}
+
+/// function classifies the cause of a connect error. reqwest reports DNS and TLS failures as connect errors and the errors below it
+/// (hyper connector, native-tls) are not public types, so this is a best-effort heuristic on the messages of the source chain:
+/// `dns error` / `failed to lookup address` of hyper resolver, or a `tls`, `ssl` or `certificate` word of the TLS backend.
+/// The message of `err` itself is skipped (it contains the url, which may contain any of the words)
+fn classify_connect_failure(err: &(dyn StdError + 'static)) -> ConnectFailure{
+    let mut messages = vec![];
+    let mut current = err.source();
+    while let Some(err) = current{
+        messages.push(err.to_string().to_lowercase());
+        current = err.source();
+    }
+
+    if messages.iter().any(|message| message.contains("dns error") || message.contains("failed to lookup address")){
+        ConnectFailure::Dns
+    }else if messages.iter().any(|message| message.split(|c: char| !c.is_ascii_alphanumeric()).any(|word| matches!(word, "tls" | "ssl" | "certificate"))){
+        ConnectFailure::Tls
+    }else{
+        ConnectFailure::Other
+    }
+}
+
+impl From<reqwest::Error> for ClientError{
+    /// classify reqwest error. DNS and TLS failures are reported by reqwest as connect errors, so the source chain is inspected
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout(){
+            ClientError::TimeoutError(err)
+        }else if err.is_builder(){
+            ClientError::BuilderError(Box::new(err))
+        }else if err.is_status(){
+            ClientError::from_status(err.status().map(|status| status.as_u16()).unwrap_or(0), "")
+        }else if err.is_connect(){
+            match classify_connect_failure(&err){
+                ConnectFailure::Dns => ClientError::DnsError(err),
+                ConnectFailure::Tls => ClientError::TlsError(err),
+                ConnectFailure::Other => ClientError::ConnectError(err)
+            }
+        }else if err.is_decode() || err.is_body(){
+            ClientError::DecodeError(err)
+        }else{
+            ClientError::RequestError(err)
+        }
+    }
+}
+
+impl fmt::Display for ErrorCategory{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self{
+            ErrorCategory::Serialization => "serialization",
+            ErrorCategory::Connect => "connect",
+            ErrorCategory::Timeout => "timeout",
+            ErrorCategory::Dns => "dns",
+            ErrorCategory::Tls => "tls",
+            ErrorCategory::Http4xx => "http_4xx",
+            ErrorCategory::Http5xx => "http_5xx",
+            ErrorCategory::HttpOther => "http_other",
+            ErrorCategory::Decode => "decode",
+            ErrorCategory::InvalidConfig => "invalid_config",
+            ErrorCategory::Request => "request",
+            ErrorCategory::Io => "io",
+        };
+        f.pad(name)
+    }
+}
+
+
+impl FromStr for ErrorCategory{
+    type Err = ClientError;
+
+    /// parse error category by its name in the report (e.g., `connect` or `http_5xx`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let category = match s.trim().to_lowercase().as_str(){
+            "serialization" => ErrorCategory::Serialization,
+            "connect" => ErrorCategory::Connect,
+            "timeout" => ErrorCategory::Timeout,
+            "dns" => ErrorCategory::Dns,
+            "tls" => ErrorCategory::Tls,
+            "http_4xx" => ErrorCategory::Http4xx,
+            "http_5xx" => ErrorCategory::Http5xx,
+            "http_other" => ErrorCategory::HttpOther,
+            "decode" => ErrorCategory::Decode,
+            "invalid_config" => ErrorCategory::InvalidConfig,
+            "request" => ErrorCategory::Request,
+            "io" => ErrorCategory::Io,
+            _ => return Err(ClientError::InvalidConfig(format!("unknown error category `{s}`")))
+        };
+        Ok(category)
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_from_status(){
+        let err = ClientError::from_status(404, "not found");
+        assert_eq!(err.category(), ErrorCategory::Http4xx);
+        assert_eq!(ClientError::from_status(304, "").category(), ErrorCategory::HttpOther);
+        assert_eq!(ClientError::from_status(600, "").category(), ErrorCategory::HttpOther);
+
+        let long_body = "x".repeat(1000);
+        match ClientError::from_status(503, &long_body){
+            ClientError::HttpServerError { status, body } => {
+                assert_eq!(status, 503);
+                assert_eq!(body.len(), BODY_EXCERPT_LEN);
+            },
+            other => panic!("unexpected error {other:?}")
+        }
+    }
+
+    #[test]
+    fn test_connect_error(){
+        // nothing should listen on port 1
+        let err = reqwest::blocking::Client::new().get("http://127.0.0.1:1/").send().unwrap_err();
+        assert_eq!(ClientError::from(err).category(), ErrorCategory::Connect);
+    }
+
+    #[test]
+    fn test_invalid_url(){
+        let err = reqwest::blocking::Client::new().get("not a url").send().unwrap_err();
+        let err = ClientError::from(err);
+        assert_eq!(err.category(), ErrorCategory::InvalidConfig);
+        // the reqwest error is kept as the source
+        assert!(err.source().is_some_and(|source| source.is::<reqwest::Error>()));
+    }
+
+    /// error with a message and an optional source (to build source chains of connect errors)
+    #[derive(Debug)]
+    struct ChainError(&'static str, Option<Box<ChainError>>);
+
+    impl fmt::Display for ChainError{
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.0)
+        }
+    }
+
+    impl StdError for ChainError{
+        fn source(&self) -> Option<&(dyn StdError + 'static)> {
+            self.1.as_deref().map(|err| err as _)
+        }
+    }
+
+    fn chain(messages: &[&'static str]) -> ChainError{
+        messages.iter().rev().fold(None, |source, message| Some(ChainError(message, source.map(Box::new)))).unwrap()
+    }
+
+    #[test]
+    fn test_classify_connect_failure(){
+        let dns = chain(&["error sending request for url (http://no-such-host/)", "error trying to connect: dns error", "failed to lookup address information: Name or service not known"]);
+        assert_eq!(classify_connect_failure(&dns), ConnectFailure::Dns);
+
+        let tls = chain(&["error sending request for url (https://localhost/)", "error trying to connect: error:0A000086:SSL routines::certificate verify failed"]);
+        assert_eq!(classify_connect_failure(&tls), ConnectFailure::Tls);
+
+        // the url of the request and words which only contain `ssl` are not TLS failures
+        let refused = chain(&["error sending request for url (http://ssl.example.com/tls)", "error trying to connect: tcp connect error", "Connection refused (os error 111)"]);
+        assert_eq!(classify_connect_failure(&refused), ConnectFailure::Other);
+        let other = chain(&["error sending request", "lossless compression is not supported"]);
+        assert_eq!(classify_connect_failure(&other), ConnectFailure::Other);
+    }
+}
@@ -0,0 +1,307 @@
+use std::{fs::File, io::{self, BufWriter, Write}, path::{Path, PathBuf}, str::FromStr, sync::{atomic::{AtomicBool, AtomicU64, Ordering}, mpsc::{self, Receiver, SyncSender, TrySendError}, Mutex, RwLock}, thread::{self, JoinHandle}, time::SystemTime};
+
+use serde::Serialize;
+
+use crate::{error::ClientError, gen::{TaskInfo, TaskKind}, observer::TaskObserver, result::TaskResult};
+
+
+/// maximum number of events waiting for the writer thread. Events are dropped (and counted) when the buffer is full, so workers are never blocked
+const EVENT_BUFFER: usize = 10_000;
+
+/// columns of CSV event log (same fields as NDJSON)
+const CSV_HEADER: &str = "timestamp_unix_ms,worker,kind,table_id,order_id,status,latency_ms,bytes,attempts,error,message";
+
+
+/// File format of the event log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventFormat{
+    Csv,
+
+    /// one JSON object per line
+    Ndjson
+}
+
+/// Event log file given on command line as `FORMAT=PATH` (e.g., `csv=events.csv`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventLogOutput{
+    pub format: EventFormat,
+    pub path: PathBuf
+}
+
+impl FromStr for EventLogOutput{
+    type Err = ClientError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ClientError::InvalidConfig(format!("invalid event log `{s}` (expected csv=PATH or ndjson=PATH)"));
+
+        let (format, path) = s.split_once('=').ok_or_else(invalid)?;
+        let format = match format.trim().to_lowercase().as_str(){
+            "csv" => EventFormat::Csv,
+            "ndjson" | "jsonl" => EventFormat::Ndjson,
+            _ => return Err(invalid())
+        };
+        if path.trim().is_empty(){
+            return Err(invalid());
+        }
+        Ok(Self { format, path: PathBuf::from(path.trim()) })
+    }
+}
+
+
+/// One completed request in the event log
+#[derive(Debug, Clone, Serialize)]
+pub struct Event{
+    /// time when the request was started (milliseconds since unix epoch)
+    pub timestamp_unix_ms: u64,
+
+    /// name of the thread which executed the request
+    pub worker: String,
+
+    pub kind: TaskKind,
+    pub table_id: i16,
+    pub order_id: Option<i32>,
+    pub status: Option<u16>,
+    pub latency_ms: f64,
+    pub bytes: usize,
+    pub attempts: u32,
+
+    /// error category and message (if the request failed)
+    pub error: Option<String>,
+    pub message: Option<String>
+}
+
+impl Event{
+    /// init function to build event from the result of the task (on the thread which executed it)
+    pub fn from_result(result: &TaskResult) -> Self{
+        let current = thread::current();
+        Self {
+            timestamp_unix_ms: result.started_at.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+            worker: current.name().map(str::to_string).unwrap_or_else(|| format!("{:?}", current.id())),
+            kind: result.kind,
+            table_id: result.table_id,
+            order_id: result.order_id,
+            status: result.status,
+            latency_ms: result.latency.as_secs_f64() * 1000.0,
+            bytes: result.bytes,
+            attempts: result.attempts,
+            error: result.error_category().map(|category| category.to_string()),
+            message: result.outcome.as_ref().err().map(ClientError::to_string)
+        }
+    }
+
+    /// function writes the event as a CSV row (with `CSV_HEADER` columns)
+    fn write_csv(&self, writer: &mut impl Write) -> io::Result<()>{
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        writeln!(writer, "{},{},{},{},{},{},{:.3},{},{},{},{}",
+            self.timestamp_unix_ms, csv_field(&self.worker), self.kind.name(), self.table_id,
+            optional(self.order_id.map(|x| x.to_string())), optional(self.status.map(|x| x.to_string())),
+            self.latency_ms, self.bytes, self.attempts,
+            optional(self.error.clone()), csv_field(self.message.as_deref().unwrap_or_default()))
+    }
+}
+
+/// function quotes CSV field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String{
+    if value.contains([',', '"', '\n', '\r']){
+        format!("\"{}\"", value.replace('"', "\"\""))
+    }else{
+        value.to_string()
+    }
+}
+
+
+/// Observer which streams every completed request into event log file. Workers send events through a bounded channel
+/// and a dedicated thread writes them, so events are not kept in memory. Sending never blocks (workers may run on async runtime)
+pub struct EventLog{
+    sender: RwLock<Option<SyncSender<Event>>>,
+    writer: Mutex<Option<JoinHandle<Result<(), ClientError>>>>,
+
+    /// number of events dropped because the writer thread could not keep up
+    dropped: AtomicU64,
+
+    /// set when the writer thread stopped (to warn only once)
+    writer_stopped: AtomicBool
+}
+
+impl EventLog{
+    /// init function to create (or truncate) event log file and start the writer thread
+    pub fn create(path: impl AsRef<Path>, format: EventFormat) -> Result<Self, ClientError>{
+        Self::with_buffer(path, format, EVENT_BUFFER)
+    }
+
+    /// init function to create event log with `buffer` events waiting for the writer thread
+    fn with_buffer(path: impl AsRef<Path>, format: EventFormat, buffer: usize) -> Result<Self, ClientError>{
+        let mut file = BufWriter::new(File::create(path)?);
+        if format == EventFormat::Csv{
+            writeln!(file, "{CSV_HEADER}")?;
+        }
+
+        let (sender, receiver) = mpsc::sync_channel(buffer);
+        let writer = thread::Builder::new()
+            .name("event-log".to_string())
+            .spawn(move || write_events(receiver, file, format))?;
+        Ok(Self {
+            sender: RwLock::new(Some(sender)),
+            writer: Mutex::new(Some(writer)),
+            dropped: AtomicU64::new(0),
+            writer_stopped: AtomicBool::new(false)
+        })
+    }
+
+    /// function returns number of events dropped because the buffer was full
+    pub fn dropped(&self) -> u64{
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// function waits until all events are written to the file. Should be called when the load test is completed (later events are ignored)
+    pub fn finish(&self) -> Result<(), ClientError>{
+        // the writer thread stops when the channel is closed
+        self.sender.write().expect("event log is poisoned").take();
+        let Some(writer) = self.writer.lock().expect("event log is poisoned").take() else { return Ok(()) };
+
+        let dropped = self.dropped();
+        if dropped > 0{
+            tracing::warn!("{dropped} events were dropped from event log (writer thread could not keep up)");
+        }
+        writer.join().expect("event log writer panicked")
+    }
+}
+
+impl Drop for EventLog{
+    /// the events are written even if `finish` is not called (e.g., the load test failed)
+    fn drop(&mut self){
+        if let Err(err) = self.finish(){
+            tracing::warn!("cannot write event log: {err}");
+        }
+    }
+}
+
+/// writer thread of the event log. It returns the first error (the remaining events are discarded)
+fn write_events(receiver: Receiver<Event>, mut file: BufWriter<File>, format: EventFormat) -> Result<(), ClientError>{
+    for event in receiver{
+        match format{
+            EventFormat::Csv => event.write_csv(&mut file)?,
+            EventFormat::Ndjson => {
+                serde_json::to_writer(&mut file, &event).map_err(|err| ClientError::SerializationError(Box::new(err)))?;
+                file.write_all(b"\n")?;
+            }
+        }
+    }
+    Ok(file.flush()?)
+}
+
+impl TaskObserver for EventLog{
+    fn on_task_complete(&self, _task: &TaskInfo, result: &TaskResult){
+        if let Some(sender) = self.sender.read().expect("event log is poisoned").as_ref(){
+            match sender.try_send(Event::from_result(result)){
+                Ok(()) => {},
+                Err(TrySendError::Full(_)) => { self.dropped.fetch_add(1, Ordering::Relaxed); },
+                Err(TrySendError::Disconnected(_)) => {
+                    if !self.writer_stopped.swap(true, Ordering::Relaxed){
+                        tracing::warn!("cannot write event log (writer thread stopped)");
+                    }
+                }
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test{
+    use std::time::Duration;
+
+    use crate::test_support;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_event_log_output(){
+        let output: EventLogOutput = "ndjson=out/events.ndjson".parse().unwrap();
+        assert_eq!(output, EventLogOutput { format: EventFormat::Ndjson, path: PathBuf::from("out/events.ndjson") });
+        assert_eq!("CSV=events.csv".parse::<EventLogOutput>().unwrap().format, EventFormat::Csv);
+        assert!("xml=events.xml".parse::<EventLogOutput>().is_err());
+        assert!("events.csv".parse::<EventLogOutput>().is_err());
+    }
+
+    #[test]
+    fn test_csv_field(){
+        assert_eq!(csv_field("timeout"), "timeout");
+        assert_eq!(csv_field("a, \"b\""), "\"a, \"\"b\"\"\"");
+    }
+
+    /// function writes results from several threads into event log and returns the lines of the file with number of dropped events
+    fn write_log_with_buffer(format: EventFormat, results: Vec<TaskResult>, buffer: usize) -> (Vec<String>, u64){
+        let path = std::env::temp_dir().join(format!("events-test-{}-{format:?}-{buffer}", std::process::id()));
+        let log = EventLog::with_buffer(&path, format, buffer).unwrap();
+
+        let task = test_support::task(TaskKind::GetAll);
+        thread::scope(|scope| {
+            for result in &results{
+                scope.spawn(|| log.on_task_complete(&task, result));
+            }
+        });
+        log.finish().unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        (content.lines().map(str::to_string).collect(), log.dropped())
+    }
+
+    fn write_log(format: EventFormat, results: Vec<TaskResult>) -> Vec<String>{
+        let (lines, dropped) = write_log_with_buffer(format, results, EVENT_BUFFER);
+        assert_eq!(dropped, 0);
+        lines
+    }
+
+    fn results() -> Vec<TaskResult>{
+        let task = test_support::table_task(TaskKind::GetOne, 3, 7);
+        (0..100)
+            .map(|_| TaskResult::new(&task, SystemTime::now(), Duration::from_millis(12), Some(500), Err(ClientError::from_status(500, "boom, \"really\""))))
+            .collect()
+    }
+
+    #[test]
+    fn test_csv_event_log(){
+        let lines = write_log(EventFormat::Csv, results());
+        assert_eq!(lines.len(), 101);
+        assert_eq!(lines[0], CSV_HEADER);
+        assert!(lines[1].contains(",GET_ONE,3,7,500,12.000,0,1,http_5xx,\"HTTP server error (status 500): boom, \"\"really\"\"\""), "{}", lines[1]);
+    }
+
+    #[test]
+    fn test_ndjson_event_log(){
+        let lines = write_log(EventFormat::Ndjson, results());
+        assert_eq!(lines.len(), 100);
+
+        let event: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(event["kind"], "GET_ONE");
+        assert_eq!(event["order_id"], 7);
+        assert_eq!(event["status"], 500);
+        assert_eq!(event["error"], "http_5xx");
+        assert!(event["worker"].is_string());
+    }
+
+    #[test]
+    fn test_full_buffer_drops_events(){
+        // workers are not blocked by the writer thread, so every event is either written or counted as dropped
+        let (lines, dropped) = write_log_with_buffer(EventFormat::Ndjson, results(), 1);
+        assert_eq!(lines.len() as u64 + dropped, 100);
+    }
+
+    #[test]
+    fn test_drop_writes_events(){
+        let path = std::env::temp_dir().join(format!("events-test-{}-drop", std::process::id()));
+        let log = EventLog::create(&path, EventFormat::Ndjson).unwrap();
+        let task = test_support::task(TaskKind::GetAll);
+        for result in &results(){
+            log.on_task_complete(&task, result);
+        }
+
+        // finish is not called (e.g., the load test failed)
+        drop(log);
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(content.lines().count(), 100);
+    }
+}
@@ -1,11 +1,15 @@
-use rand::{self, Rng};
+use std::{fmt, str::FromStr, sync::Arc};
+
+use rand::{self, distributions::{Distribution, WeightedIndex}, rngs::StdRng, Rng, SeedableRng};
 use restaurant_server::model::TableOrdersRequest;
+use serde::{Deserialize, Serialize};
 
-use crate::{config::Config, error::ClientError};
+use crate::{config::Config, error::ClientError, table::TableSelector, tracker::OrderTracker};
 
 
 /// We can categorize task for each server function (i.e., Add orders, Get one order, Get All order (of a table) and Remove order)
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum TaskKind{
     Add,
     GetOne,
@@ -15,6 +19,9 @@ pub enum TaskKind{
 
 
 impl TaskKind{
+    /// all task kinds (in the same order as `select`). Used for reporting
+    pub const ALL: [TaskKind; 4] = [TaskKind::Add, TaskKind::GetOne, TaskKind::GetAll, TaskKind::Remove];
+
     /// function to get TaskKind base on the input random index. Used for testing
     pub fn select(index: u8) -> TaskKind{
         match index % 4{
@@ -25,75 +32,206 @@ impl TaskKind{
             _ => TaskKind::Add
         }
     }
+
+    /// name of the task kind used in config and files (e.g., `GET_ONE`)
+    pub fn name(&self) -> &'static str{
+        match self{
+            TaskKind::Add => "ADD",
+            TaskKind::GetOne => "GET_ONE",
+            TaskKind::GetAll => "GET_ALL",
+            TaskKind::Remove => "REMOVE",
+        }
+    }
+}
+
+impl fmt::Display for TaskKind{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self{
+            TaskKind::Add => "Add",
+            TaskKind::GetOne => "GetOne",
+            TaskKind::GetAll => "GetAll",
+            TaskKind::Remove => "Remove",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for TaskKind{
+    type Err = ClientError;
+
+    /// parse task kind name used in config (e.g., `ADD`, `GET_ONE`, `GET_ALL`, `REMOVE`). Case, `_` and `-` are ignored
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name: String = s.trim().chars().filter(|c| *c != '_' && *c != '-').collect();
+        match name.to_uppercase().as_str(){
+            "ADD" => Ok(TaskKind::Add),
+            "GETONE" => Ok(TaskKind::GetOne),
+            "GETALL" => Ok(TaskKind::GetAll),
+            "REMOVE" => Ok(TaskKind::Remove),
+            _ => Err(ClientError::InvalidConfig(format!("unknown task kind `{s}`")))
+        }
+    }
+}
+
+/// Relative weight of each task kind. The generator selects task kind proportionally to the weights
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskWeights([u32; 4]);
+
+impl TaskWeights{
+    /// function returns weight of the task kind
+    pub fn weight(&self, kind: TaskKind) -> u32{
+        self.0[TaskKind::ALL.iter().position(|x| *x == kind).unwrap_or(0)]
+    }
+
+    /// function converts weights into distribution of indices of `TaskKind::ALL`
+    fn distribution(&self) -> Result<WeightedIndex<u32>, ClientError>{
+        WeightedIndex::new(self.0).map_err(|err| ClientError::InvalidConfig(format!("invalid task weights: {err}")))
+    }
+}
+
+impl Default for TaskWeights{
+    /// all task kinds are equally likely
+    fn default() -> Self {
+        Self([1; 4])
+    }
 }
 
+impl FromStr for TaskWeights{
+    type Err = ClientError;
+
+    /// parse weights from config string, e.g. `ADD=50,GET_ALL=30,GET_ONE=15,REMOVE=5`. Task kinds which are not listed have zero weight
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut weights = [0; 4];
+
+        for entry in s.split(',').map(str::trim).filter(|entry| !entry.is_empty()){
+            let (kind, weight) = entry.split_once('=')
+                .ok_or_else(|| ClientError::InvalidConfig(format!("task weight entry `{entry}` is not in KIND=WEIGHT form")))?;
+            let kind: TaskKind = kind.parse()?;
+            let weight: u32 = weight.trim().parse()
+                .map_err(|_| ClientError::InvalidConfig(format!("invalid task weight in `{entry}`")))?;
+
+            weights[TaskKind::ALL.iter().position(|x| *x == kind).unwrap_or(0)] = weight;
+        }
+
+        let weights = Self(weights);
+        weights.distribution()?;
+        Ok(weights)
+    }
+}
+
+
+/// Version of the JSON schema of `TaskInfo`. It must be increased when the schema is changed incompatibly
+pub const TASK_SCHEMA_VERSION: u32 = 1;
+
 /// Task info stores 1.) what kind of task and the parameters it need to execute the task
-#[derive(Debug)]
+///
+/// JSON schema (version `TASK_SCHEMA_VERSION`), e.g. `{"kind":"ADD","table_id":3,"orders":{"table_id":3,"orders":[...]},"delay_time":0}`.
+/// `endpoint_url` is not serialized. It is derived from config when the task is read back (see `workload::RecordedTask`), so the same task works against different servers
+#[derive(Debug, Serialize)]
 pub struct TaskInfo{
-    /// task category
+    /// task category (`ADD`, `GET_ONE`, `GET_ALL` or `REMOVE`)
     pub kind: TaskKind,
 
     /// target table id 
     pub table_id: i16,
 
     /// target order id (for get one order and remove order function)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub order_id: Option<i32>,
 
     /// table order request object for add operation
+    #[serde(rename = "orders", skip_serializing_if = "Option::is_none")]
     pub table_orders_request: Option<TableOrdersRequest>,
 
+    #[serde(skip)]
     pub endpoint_url: String,
 
+    /// delay time (seconds) after the task
     pub delay_time: u64
 }
 
+/// function builds url of the task endpoint
+fn endpoint_url(config: &Config, table_id: i16, order_id: Option<i32>) -> String{
+    match order_id{
+        Some(order_id) => format!("{}/api/v1/tables/{}/orders/{}", config.get_endpoint(), table_id, order_id),
+        None => format!("{}/api/v1/tables/{}/orders", config.get_endpoint(), table_id)
+    }
+}
+
 impl TaskInfo{
-    /// init function to generate add task. the delay time and endpoint url are determined here.
-    pub fn new_add_task(table_id: i16, table_orders: TableOrdersRequest, config: &Config) -> Self{
-        let delay_time = rand::thread_rng().gen_range(0..=config.get_max_delay_time_in_secs());
-        let endpoint_url = format!("{}/api/v1/tables/{}/orders", config.get_endpoint(), table_id);
+    /// init function to generate add task. the delay time (drawn from `rng`) and endpoint url are determined here.
+    pub fn new_add_task(table_id: i16, table_orders: TableOrdersRequest, config: &Config, rng: &mut impl Rng) -> Self{
+        let delay_time = rng.gen_range(0..=config.get_max_delay_time_in_secs());
+        let endpoint_url = endpoint_url(config, table_id, None);
         Self { kind: TaskKind::Add, table_id, order_id: None, table_orders_request: Some(table_orders), endpoint_url, delay_time }
     }
 
     /// init function to generate get all orders from specific table task. The delay time and endpoint url are determined here.
-    pub fn new_get_all_task( table_id: i16, config: &Config) -> Self{
-        let delay_time = rand::thread_rng().gen_range(0..=config.get_max_delay_time_in_secs());
-        let endpoint = format!("{}/api/v1/tables/{}/orders", config.get_endpoint(), table_id);
+    pub fn new_get_all_task( table_id: i16, config: &Config, rng: &mut impl Rng) -> Self{
+        let delay_time = rng.gen_range(0..=config.get_max_delay_time_in_secs());
+        let endpoint = endpoint_url(config, table_id, None);
         
         Self {  kind: TaskKind::GetAll, table_id, order_id: None, table_orders_request: None, endpoint_url: endpoint, delay_time }
     }
 
     
     /// init function to generate get specific order task. The delay time and endpoint url are determined here.
-    pub fn new_get_one_task(table_id: i16, order_id: i32, config: &Config) -> Self{
-        let delay_time = rand::thread_rng().gen_range(0..=config.get_max_delay_time_in_secs());
-        let endpoint = format!("{}/api/v1/tables/{}/orders/{}", config.get_endpoint(), table_id, order_id);
+    pub fn new_get_one_task(table_id: i16, order_id: i32, config: &Config, rng: &mut impl Rng) -> Self{
+        let delay_time = rng.gen_range(0..=config.get_max_delay_time_in_secs());
+        let endpoint = endpoint_url(config, table_id, Some(order_id));
         
         Self {  kind: TaskKind::GetOne, table_id, order_id: Some(order_id), table_orders_request: None, endpoint_url: endpoint, delay_time }
     }
 
     /// utility function to generate order removal task. The delay time and endpoint url are determined here.
-    pub fn new_remove_task(table_id: i16, order_id: i32, config: &Config) -> Self{
-        let delay_time = rand::thread_rng().gen_range(0..=config.get_max_delay_time_in_secs());
-        let endpoint = format!("{}/api/v1/tables/{}/orders/{}", config.get_endpoint(), table_id, order_id);
+    pub fn new_remove_task(table_id: i16, order_id: i32, config: &Config, rng: &mut impl Rng) -> Self{
+        let delay_time = rng.gen_range(0..=config.get_max_delay_time_in_secs());
+        let endpoint = endpoint_url(config, table_id, Some(order_id));
         
         Self {  kind: TaskKind::Remove, table_id, order_id: Some(order_id), table_orders_request: None, endpoint_url: endpoint, delay_time }
     }
 
+    /// init function to build task read from a file (e.g., recorded workload). The endpoint url is built from config and the parameters are validated
+    pub(crate) fn from_fields(kind: TaskKind, table_id: i16, order_id: Option<i32>, table_orders_request: Option<TableOrdersRequest>, delay_time: u64, config: &Config) -> Result<Self, ClientError>{
+        let endpoint_url = endpoint_url(config, table_id, order_id);
+        let task = Self { kind, table_id, order_id, table_orders_request, endpoint_url, delay_time };
+        task.validate()?;
+        Ok(task)
+    }
+
+    /// function checks that parameters of the task match its kind (e.g., when the task is read from a file)
+    pub fn validate(&self) -> Result<(), ClientError>{
+        let invalid = |reason: &str| Err(ClientError::InvalidConfig(format!("invalid {} task (table {}): {reason}", self.kind, self.table_id)));
+
+        if self.table_id < 1{
+            return invalid("table id must be positive");
+        }
+        match (self.kind, self.order_id, &self.table_orders_request){
+            (TaskKind::Add, None, Some(orders)) if orders.table_id != self.table_id => invalid("orders are for another table"),
+            (TaskKind::Add, None, Some(orders)) if orders.orders.is_empty() => invalid("no orders"),
+            (TaskKind::Add, None, Some(_)) | (TaskKind::GetAll, None, None) => Ok(()),
+            (TaskKind::GetOne | TaskKind::Remove, Some(order_id), None) if order_id < 1 => invalid("order id must be positive"),
+            (TaskKind::GetOne | TaskKind::Remove, Some(_), None) => Ok(()),
+            (TaskKind::Add, _, None) => invalid("orders are missing"),
+            (TaskKind::GetOne | TaskKind::Remove, None, _) => invalid("order id is missing"),
+            (TaskKind::Add | TaskKind::GetAll, Some(_), _) => invalid("unexpected order id"),
+            (_, _, Some(_)) => invalid("unexpected orders")
+        }
+    }
+
 
     /// utility function to get json request from table order request (for add order operation) 
     pub fn get_table_order_request_json_string(&self) -> Result<String, ClientError>{
         self.table_orders_request.as_ref()
-            .ok_or_else(|| ClientError::SerializationError)
-            .and_then(|order| order.to_json().map_err(|_| ClientError::SerializationError))
+            .ok_or_else(|| ClientError::SerializationError("add orders task has no table orders request".into()))
+            .and_then(|order| order.to_json().map_err(|err| ClientError::SerializationError(Box::new(err))))
     }
 }
 
 
 /// function to generate one add TableOrderRequest (add new orders) task
-fn gen_add_orders_task(config: &Config) -> TaskInfo{
-    let table_id = rand::thread_rng().gen_range(1..=config.get_max_tables());
-    let num_orders = rand::thread_rng().gen_range(1..=config.get_max_orders_per_request());
+fn gen_add_orders_task(config: &Config, tables: &mut TableSelector, rng: &mut impl Rng) -> TaskInfo{
+    let table_id = tables.select(rng);
+    let num_orders = rng.gen_range(1..=config.get_max_orders_per_request());
     let mut order = TableOrdersRequest::new(table_id);
 
     // more cleaner than for_each
@@ -101,55 +239,123 @@ fn gen_add_orders_task(config: &Config) -> TaskInfo{
         order.add_order(format!("item-{table_id}-{i}").as_str(), format!("note-{table_id}-{i}").as_str());
     };
 
-    TaskInfo::new_add_task( table_id, order, config)
+    TaskInfo::new_add_task( table_id, order, config, rng)
 }
 
-/// function to generate one add TableOrderRequest (add new orders) task
-fn gen_remove_order_task(config: &Config) -> TaskInfo{
-    let order_id = rand::thread_rng().gen_range(1..=config.get_max_order_id());
-    TaskInfo::new_remove_task( 1, order_id, config)
+/// function to generate one remove order task
+fn gen_remove_order_task(config: &Config, tables: &mut TableSelector, rng: &mut impl Rng) -> TaskInfo{
+    let table_id = tables.select(rng);
+    let order_id = rng.gen_range(1..=config.get_max_order_id());
+    TaskInfo::new_remove_task( table_id, order_id, config, rng)
 }
 
 
-/// function to generate one add TableOrderRequest (add new orders) task
-fn gen_get_all_orders_task(config: &Config) -> TaskInfo{
-    let table_id = rand::thread_rng().gen_range(1..=config.get_max_tables());
-    TaskInfo::new_get_all_task( table_id, config)
+/// function to generate one get all orders (of a table) task
+fn gen_get_all_orders_task(config: &Config, tables: &mut TableSelector, rng: &mut impl Rng) -> TaskInfo{
+    let table_id = tables.select(rng);
+    TaskInfo::new_get_all_task( table_id, config, rng)
 }
 
-/// function to generate one add TableOrderRequest (add new orders) task
-fn gen_get_one_order_task(config: &Config) -> TaskInfo{
-    let table_id = rand::thread_rng().gen_range(1..=config.get_max_tables());
-    let order_id = rand::thread_rng().gen_range(1..=config.get_max_order_id());
-    TaskInfo::new_get_one_task( table_id, order_id, config)
+/// function to generate one get specific order task
+fn gen_get_one_order_task(config: &Config, tables: &mut TableSelector, rng: &mut impl Rng) -> TaskInfo{
+    let table_id = tables.select(rng);
+    let order_id = rng.gen_range(1..=config.get_max_order_id());
+    TaskInfo::new_get_one_task( table_id, order_id, config, rng)
+}
+
+
+
+/// generate single task of the given kind and its necessary parameters
+fn gen_task(kind: TaskKind, config: &Config, tables: &mut TableSelector, rng: &mut impl Rng) -> TaskInfo {
+    match kind{
+        TaskKind::Add => gen_add_orders_task(config, tables, rng),
+        TaskKind::GetOne => gen_get_one_order_task(config, tables, rng),
+        TaskKind::GetAll => gen_get_all_orders_task(config, tables, rng),
+        TaskKind::Remove => gen_remove_order_task(config, tables, rng),
+    }
+}
+
+/// Lazy generator of random tasks. It is an infinite iterator, so tasks are generated only when they are needed.
+/// Task kinds are selected according to `TASK_WEIGHTS` and tables according to `TABLE_DISTRIBUTION` in config. All random values are drawn from one RNG seeded by `SEED`,
+/// so the same seed always generates the same tasks (except stateful workload which depends on responses)
+pub struct TaskGenerator{
+    config: Config,
+    kinds: WeightedIndex<u32>,
+    tables: TableSelector,
+    seed: u64,
+    rng: StdRng,
+
+    /// orders created during the load test (stateful workload). If it exists, get one order and remove order tasks target known orders
+    tracker: Option<Arc<OrderTracker>>
 }
 
+impl TaskGenerator{
+    /// init function. If seed is not in config, a random seed is used (see `seed`)
+    pub fn new(config: &Config) -> Result<Self, ClientError>{
+        let kinds = config.get_task_weights()?.distribution()?;
+        let tables = TableSelector::new(&config.get_table_distribution()?, config.get_max_tables());
+        let seed = config.get_seed().unwrap_or_else(|| rand::thread_rng().gen());
+        Ok(Self { config: config.clone(), kinds, tables, seed, rng: StdRng::seed_from_u64(seed), tracker: None })
+    }
+
+    /// seed of the generator. Use it as `SEED` to generate the same tasks again
+    pub fn seed(&self) -> u64{
+        self.seed
+    }
+
+    /// function makes generator stateful. The tracker must observe results of the generated tasks
+    pub fn with_tracker(mut self, tracker: Arc<OrderTracker>) -> Self{
+        self.tracker = Some(tracker);
+        self
+    }
+
+    /// generate get one order / remove order task for known order (if exists)
+    fn gen_known_order_task(&mut self, kind: TaskKind) -> Option<TaskInfo>{
+        let tracker = self.tracker.as_ref()?;
 
+        match kind{
+            TaskKind::GetOne => tracker.pick(&mut self.rng).map(|(table_id, order_id)| TaskInfo::new_get_one_task(table_id, order_id, &self.config, &mut self.rng)),
+            TaskKind::Remove => tracker.take(&mut self.rng).map(|(table_id, order_id)| TaskInfo::new_remove_task(table_id, order_id, &self.config, &mut self.rng)),
+            _ => None
+        }
+    }
+}
 
-/// randomly generate single task and its necessary parameters
-fn gen_single_task(config: &Config) -> TaskInfo {
-    let mut rng = rand::thread_rng();
-    match TaskKind::select(rng.gen_range(0..4)){
-        TaskKind::Add => gen_add_orders_task(config),
-        TaskKind::GetOne => gen_get_one_order_task(config),
-        TaskKind::GetAll => gen_get_all_orders_task(config),
-        TaskKind::Remove => gen_remove_order_task(config),
+impl Iterator for TaskGenerator{
+    type Item = TaskInfo;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let kind = TaskKind::ALL[self.kinds.sample(&mut self.rng)];
+        let task = match self.gen_known_order_task(kind){
+            Some(task) => task,
+            None => gen_task(kind, &self.config, &mut self.tables, &mut self.rng)
+        };
+        Some(task)
     }
 }
 
-/// function generates random tasks 
-pub fn gen_all_tasks(config: &Config) -> Vec<TaskInfo>{
-    (0..config.get_max_request_tasks()).map(|_| gen_single_task(config)).collect()
+/// function generates `MAX_REQUEST_TASKS` random tasks 
+pub fn gen_all_tasks(config: &Config) -> Result<Vec<TaskInfo>, ClientError>{
+    Ok(TaskGenerator::new(config)?.take(config.get_max_request_tasks().max(0) as usize).collect())
 }
 
 
 #[cfg(test)]
 mod test{
+    use std::time::{Duration, SystemTime};
+
     use crate::config::Config;
+    use crate::{observer::TaskObserver, result::TaskResult};
+
+    use crate::{table::TableDistribution, test_support};
 
     use super::TaskKind;
     use super::*;
 
+    fn uniform_tables(config: &Config) -> TableSelector{
+        TableSelector::new(&TableDistribution::Uniform, config.get_max_tables())
+    }
+
     #[test]
     fn test_select_task_kind(){
         let results : Vec<TaskKind> = (0..8).map(TaskKind::select).collect();
@@ -166,19 +372,134 @@ mod test{
 
     }
 
+    #[test]
+    fn test_parse_task_kind(){
+        assert_eq!("ADD".parse::<TaskKind>().unwrap(), TaskKind::Add);
+        assert_eq!("get_one".parse::<TaskKind>().unwrap(), TaskKind::GetOne);
+        assert_eq!("GetAll".parse::<TaskKind>().unwrap(), TaskKind::GetAll);
+        assert_eq!(" remove ".parse::<TaskKind>().unwrap(), TaskKind::Remove);
+        assert!("UPDATE".parse::<TaskKind>().is_err());
+    }
+
+    #[test]
+    fn test_parse_task_weights(){
+        let weights: TaskWeights = "ADD=50,GET_ALL=30,GET_ONE=15".parse().unwrap();
+        assert_eq!(weights.weight(TaskKind::Add), 50);
+        assert_eq!(weights.weight(TaskKind::GetAll), 30);
+        assert_eq!(weights.weight(TaskKind::GetOne), 15);
+        assert_eq!(weights.weight(TaskKind::Remove), 0);
+
+        assert!("ADD=0".parse::<TaskWeights>().is_err());
+        assert!("ADD=-1".parse::<TaskWeights>().is_err());
+        assert!("ADD".parse::<TaskWeights>().is_err());
+    }
+
+    #[test]
+    fn test_generator_honours_weights(){
+        let config = test_support::config_with(&[("TASK_WEIGHTS", "ADD=3,GET_ALL=1"), ("SEED", "42")]);
+        let mut generator = TaskGenerator::new(&config).unwrap();
+
+        let tasks: Vec<TaskInfo> = generator.by_ref().take(4000).collect();
+        let adds = tasks.iter().filter(|task| task.kind == TaskKind::Add).count();
+
+        assert!(tasks.iter().all(|task| matches!(task.kind, TaskKind::Add | TaskKind::GetAll)));
+        assert!((2700..3300).contains(&adds));
+    }
+
+    #[test]
+    fn test_stateful_generator_targets_known_orders(){
+        let config = test_support::config_with(&[("TASK_WEIGHTS", "ADD=1,GET_ONE=1,REMOVE=1"), ("SEED", "42")]);
+        let tracker = Arc::new(OrderTracker::default());
+        let generator = TaskGenerator::new(&config).unwrap().with_tracker(tracker.clone());
+
+        let add = test_support::table_task(TaskKind::Add, 3, 0);
+        let body = r#"{"table_id":3,"orders":[{"order_id":12345,"table_id":3,"item_name":"item-1","note":null}]}"#;
+        tracker.on_task_complete(&add, &TaskResult::new(&add, SystemTime::now(), Duration::ZERO, Some(201), Ok(body.to_string())));
+
+        let task = generator.take(100).find(|task| task.kind != TaskKind::Add).unwrap();
+        assert_eq!(task.table_id, 3);
+        assert_eq!(task.order_id, Some(12345));
+    }
+
+    #[test]
+    fn test_same_seed_generates_same_tasks(){
+        let mut config = test_support::config();
+        config.set_seed(42);
+
+        let summary = |task: TaskInfo| (task.get_table_order_request_json_string().ok(), task.kind, task.table_id, task.order_id, task.delay_time, task.endpoint_url);
+        let first: Vec<_> = TaskGenerator::new(&config).unwrap().take(200).map(summary).collect();
+        let second: Vec<_> = TaskGenerator::new(&config).unwrap().take(200).map(summary).collect();
+        assert_eq!(first, second);
+
+        config.set_seed(43);
+        let other: Vec<_> = TaskGenerator::new(&config).unwrap().take(200).map(summary).collect();
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn test_task_json_schema(){
+        let config = test_support::config();
+        let mut orders = TableOrdersRequest::new(3);
+        orders.add_order_wihtout_note("item-3-0");
+        let mut task = TaskInfo::new_add_task(3, orders, &config, &mut test_support::rng());
+        task.delay_time = 1;
+
+        let json = serde_json::to_value(&task).unwrap();
+        assert_eq!(json["kind"], "ADD");
+        assert_eq!(json["table_id"], 3);
+        assert_eq!(json["delay_time"], 1);
+        assert!(json.get("endpoint_url").is_none());
+        assert!(json.get("order_id").is_none());
+
+        assert_eq!(json["orders"]["orders"][0]["item_name"], "item-3-0");
+
+        let built = TaskInfo::from_fields(TaskKind::Add, 3, None, task.table_orders_request.take(), 1, &config).unwrap();
+        assert_eq!(built.endpoint_url, task.endpoint_url);
+    }
+
+    #[test]
+    fn test_validate_task(){
+        let config = test_support::config();
+        let build = |kind: TaskKind, table_id: i16, order_id: Option<i32>| TaskInfo::from_fields(kind, table_id, order_id, None, 0, &config);
+
+        assert!(test_support::table_task(TaskKind::Remove, 1, 2).validate().is_ok());
+        assert!(build(TaskKind::GetAll, 4, None).is_ok());
+        assert!(build(TaskKind::GetAll, 0, None).is_err());
+        assert!(build(TaskKind::GetAll, 4, Some(1)).is_err());
+        assert!(build(TaskKind::Remove, 4, None).is_err());
+        assert!(build(TaskKind::Remove, 4, Some(-1)).is_err());
+        assert!(build(TaskKind::Add, 4, None).is_err());
+
+        let mut other_table = TableOrdersRequest::new(5);
+        other_table.add_order_wihtout_note("item-5-0");
+        let mut rng = test_support::rng();
+        assert!(TaskInfo::new_add_task(4, other_table, &config, &mut rng).validate().is_err());
+        assert!(TaskInfo::new_add_task(4, TableOrdersRequest::new(4), &config, &mut rng).validate().is_err());
+    }
+
+    #[test]
+    fn test_remove_tasks_use_all_tables(){
+        let config = test_support::config();
+        let mut tables = TableSelector::new(&TableDistribution::RoundRobin, config.get_max_tables());
+        let mut rng = test_support::rng();
+
+        let table_ids: Vec<i16> = (0..config.get_max_tables()).map(|_| gen_remove_order_task(&config, &mut tables, &mut rng).table_id).collect();
+        assert_eq!(table_ids, (1..=config.get_max_tables()).collect::<Vec<i16>>());
+    }
+
     #[test]
     fn test_gen_add_order_task(){
         let config = envy::from_env::<Config>().expect("Cannot load config from env (exit now)");
-        let result = gen_add_orders_task(&config);
+        let result = gen_add_orders_task(&config, &mut uniform_tables(&config), &mut rand::thread_rng());
         
         assert_eq!(result.kind, TaskKind::Add);
-        assert!((0..config.get_max_tables()).contains(&result.table_id));
+        assert!((1..=config.get_max_tables()).contains(&result.table_id));
 
         let order = result.table_orders_request.unwrap();
         assert_eq!(order.table_id, result.table_id);
         
-        assert!(order.orders.len() > 0);
-        assert!(order.orders.len() < config.get_max_orders_per_request() as usize);
+        assert!(!order.orders.is_empty());
+        assert!(order.orders.len() <= config.get_max_orders_per_request() as usize);
 
     }
 
@@ -186,10 +507,10 @@ mod test{
     #[test]
     fn test_gen_get_all_task(){
         let config = envy::from_env::<Config>().expect("Cannot load config from env (exit now)");
-        let result = gen_get_all_orders_task(&config);
+        let result = gen_get_all_orders_task(&config, &mut uniform_tables(&config), &mut rand::thread_rng());
         
         assert_eq!(result.kind, TaskKind::GetAll);
-        assert!((0..config.get_max_tables()).contains(&result.table_id));
+        assert!((1..=config.get_max_tables()).contains(&result.table_id));
     }
 
     
@@ -197,11 +518,11 @@ mod test{
     #[test]
     fn test_get_one_task(){
         let config = envy::from_env::<Config>().expect("Cannot load config from env (exit now)");
-        let result = gen_get_one_order_task(&config);
+        let result = gen_get_one_order_task(&config, &mut uniform_tables(&config), &mut rand::thread_rng());
         
         assert_eq!(result.kind, TaskKind::GetOne);
-        assert!((0..config.get_max_tables()).contains(&result.table_id));
-        assert!((0..config.get_max_order_id()).contains(&result.order_id.unwrap()));
+        assert!((1..=config.get_max_tables()).contains(&result.table_id));
+        assert!((1..=config.get_max_order_id()).contains(&result.order_id.unwrap()));
         
     }
 
@@ -209,11 +530,11 @@ mod test{
     #[test]
     fn test_remove_task(){
         let config = envy::from_env::<Config>().expect("Cannot load config from env (exit now)");
-        let result = gen_remove_order_task(&config);
+        let result = gen_remove_order_task(&config, &mut uniform_tables(&config), &mut rand::thread_rng());
         
         assert_eq!(result.kind, TaskKind::Remove);
-        assert!((0..config.get_max_tables()).contains(&result.table_id));
-        assert!((0..config.get_max_order_id()).contains(&result.order_id.unwrap()));
+        assert!((1..=config.get_max_tables()).contains(&result.table_id));
+        assert!((1..=config.get_max_order_id()).contains(&result.order_id.unwrap()));
         
     }
 }
\ No newline at end of file
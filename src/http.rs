@@ -0,0 +1,175 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{atomic::{AtomicBool, Ordering}, Arc},
+    thread::{self, JoinHandle},
+};
+
+use crate::error::ClientError;
+
+
+/// maximum size of request body accepted by the local servers. Larger requests are answered with 413 and the connection is closed
+pub(crate) const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+
+/// Parsed HTTP request
+pub(crate) struct HttpRequest{
+    pub(crate) method: String,
+    pub(crate) path: String,
+    pub(crate) keep_alive: bool,
+    pub(crate) body: Vec<u8>
+}
+
+fn reason(status: u16) -> &'static str{
+    match status{
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => ""
+    }
+}
+
+
+/// function reads one HTTP/1.1 request. `None` is returned if the connection is closed or the body is larger than `MAX_BODY_SIZE`
+/// (then 413 is written to `writer` and the connection should be closed)
+pub(crate) fn read_request(reader: &mut impl BufRead, writer: &mut impl Write) -> std::io::Result<Option<HttpRequest>>{
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line)? == 0{
+        return Ok(None);
+    }
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or_default().to_string(), parts.next().unwrap_or_default().to_string());
+
+    let mut headers = HashMap::new();
+    loop{
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty(){
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':'){
+            headers.insert(name.trim().to_lowercase(), value.trim().to_lowercase());
+        }
+    }
+
+    let length = headers.get("content-length").and_then(|length| length.parse().ok()).unwrap_or(0);
+    if length > MAX_BODY_SIZE{
+        let body = "request body too large\n";
+        write_response(writer, 413, &[], "text/plain; charset=utf-8", body, body.len(), false)?;
+        return Ok(None);
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    let keep_alive = headers.get("connection").map(String::as_str) != Some("close");
+    Ok(Some(HttpRequest { method, path, keep_alive, body }))
+}
+
+/// function writes response. `content_length` may differ from the body to simulate a truncated body
+pub(crate) fn write_response(stream: &mut impl Write, status: u16, headers: &[(&str, String)], content_type: &str, body: &str, content_length: usize, keep_alive: bool) -> std::io::Result<()>{
+    let headers: String = headers.iter().map(|(name, value)| format!("{name}: {value}\r\n")).collect();
+    // the response is written at once (small writes are delayed by Nagle's algorithm)
+    let response = format!("HTTP/1.1 {status} {}\r\nContent-Type: {content_type}\r\nContent-Length: {content_length}\r\nConnection: {}\r\n{headers}\r\n{body}",
+        reason(status), if keep_alive { "keep-alive" } else { "close" });
+    stream.write_all(response.as_bytes())?;
+    stream.flush()
+}
+
+
+/// TCP listener of the local servers (mock server and metrics endpoint). Accepted connections are passed to the handler
+/// on the accept thread. The listener is stopped when it is dropped
+pub(crate) struct Listener{
+    addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    accept: Option<JoinHandle<()>>
+}
+
+impl Listener{
+    /// function starts listening on the address (e.g. `127.0.0.1:0`). `name` is the name of the accept thread
+    pub(crate) fn bind(addr: &str, name: &str, handler: impl Fn(TcpStream) + Send + 'static) -> Result<Self, ClientError>{
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let accept = {
+            let shutdown = shutdown.clone();
+            thread::Builder::new().name(name.to_string()).spawn(move || {
+                for stream in listener.incoming(){
+                    if shutdown.load(Ordering::SeqCst){
+                        break;
+                    }
+                    if let Ok(stream) = stream{
+                        handler(stream);
+                    }
+                }
+            })?
+        };
+
+        Ok(Self { addr, shutdown, accept: Some(accept) })
+    }
+
+    pub(crate) fn addr(&self) -> SocketAddr{
+        self.addr
+    }
+
+    /// function blocks current thread while the listener is running
+    pub(crate) fn wait(mut self){
+        if let Some(accept) = self.accept.take(){
+            let _ = accept.join();
+        }
+    }
+}
+
+impl Drop for Listener{
+    fn drop(&mut self){
+        if let Some(accept) = self.accept.take(){
+            self.shutdown.store(true, Ordering::SeqCst);
+            // wake up the accept loop
+            let _ = TcpStream::connect(self.addr);
+            let _ = accept.join();
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test{
+    use std::io::{BufReader, Read};
+
+    use super::*;
+
+    #[test]
+    fn test_read_request(){
+        let raw = "POST /api/v1/tables/1/orders HTTP/1.1\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}";
+        let mut response = Vec::new();
+        let request = read_request(&mut BufReader::new(raw.as_bytes()), &mut response).unwrap().unwrap();
+        assert_eq!((request.method.as_str(), request.path.as_str()), ("POST", "/api/v1/tables/1/orders"));
+        assert_eq!(request.body, b"{}");
+        assert!(!request.keep_alive && response.is_empty());
+    }
+
+    #[test]
+    fn test_body_too_large(){
+        // the body is not allocated from the announced length
+        let raw = format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", usize::MAX);
+        let mut response = Vec::new();
+        assert!(read_request(&mut BufReader::new(raw.as_bytes()), &mut response).unwrap().is_none());
+        assert!(String::from_utf8(response).unwrap().starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+    }
+
+    #[test]
+    fn test_listener_passes_connections(){
+        let listener = Listener::bind("127.0.0.1:0", "test-listener", |mut stream| {
+            let _ = stream.write_all(b"hello");
+        }).unwrap();
+
+        let mut greeting = String::new();
+        TcpStream::connect(listener.addr()).unwrap().read_to_string(&mut greeting).unwrap();
+        assert_eq!(greeting, "hello");
+    }
+}
@@ -1,5 +1,25 @@
 
 pub mod gen;
 pub mod config;
+pub mod consistency;
+pub mod dashboard;
 pub mod request;
 pub mod error;
+pub mod engine;
+pub mod events;
+pub mod http;
+pub mod metrics;
+pub mod mock;
+pub mod report;
+pub mod observer;
+pub mod profile;
+pub mod result;
+pub mod retry;
+pub mod status;
+pub mod table;
+#[cfg(test)]
+mod test_support;
+pub mod timeseries;
+pub mod tracker;
+pub mod verify;
+pub mod workload;
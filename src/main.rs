@@ -1,15 +1,33 @@
 
 use restaurant_client::config::Config;
+use restaurant_client::consistency::{check_history, HistoryRecorder};
+use restaurant_client::dashboard::{Dashboard, DashboardRenderer};
+use restaurant_client::engine::{self, LoadEngine};
 use restaurant_client::error::ClientError;
-use restaurant_client::gen::{self, TaskInfo};
+use restaurant_client::events::{EventLog, EventLogOutput};
+use restaurant_client::gen::{TaskGenerator, TaskInfo};
+use restaurant_client::metrics::{LoadMetrics, MetricsServer};
+use restaurant_client::mock::{FaultRule, MockOptions, MockServer};
+use restaurant_client::observer::Observers;
+use restaurant_client::profile::parse_duration;
+use restaurant_client::report::{LoadReport, ReportOutput, RunInfo};
+use restaurant_client::result::TaskResult;
+use restaurant_client::status::ExpectedStatuses;
+use restaurant_client::timeseries::TimeSeries;
+use restaurant_client::tracker::OrderTracker;
+use restaurant_client::verify::ResponseVerifier;
+use restaurant_client::workload::{read_workload, WorkloadRecorder};
 use restaurant_client::request::{
-    execute_tasks, request_add_orders, request_get_all_orders, request_get_one_order,
+    build_client, execute_tasks, request_add_orders, request_get_all_orders, request_get_one_order,
     request_remove_order,
 };
 use restaurant_server::model::TableOrdersRequest;
 
-use clap::{Parser, Subcommand};
-use dotenvy;
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
@@ -45,7 +63,106 @@ enum Operations {
         #[arg(short, long)]
         order_id: i32,
     },
-    Load
+    Load(LoadArgs),
+    Replay(ReplayArgs),
+    MockServer(MockServerArgs)
+}
+
+#[derive(Args, Debug)]
+struct LoadArgs {
+    /// load engine: `blocking` (rayon workers) or `async` (tokio). Default is `async` for open-loop / staged load, `blocking` otherwise
+    #[arg(long)]
+    engine: Option<LoadEngine>,
+
+    /// open-loop mode: start requests at this rate (requests/sec) regardless of response time (async engine only)
+    #[arg(long)]
+    rate: Option<f64>,
+
+    /// seed of random task generation (overrides SEED in config). The same seed generates the same tasks
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// deadline of the load test, e.g. `5m` (overrides LOAD_DEADLINE_MS in config). Outstanding tasks are cancelled and reported as timed out
+    #[arg(long, value_parser = parse_duration)]
+    deadline: Option<Duration>,
+
+    /// record every executed task (as JSON lines) into this file, so the workload can be replayed later
+    #[arg(long)]
+    record: Option<PathBuf>,
+
+    /// check that 2xx responses are correct for their task kind and report violations separately
+    #[arg(long)]
+    verify: bool,
+
+    /// also write the report into a file, e.g. `json=report.json` (machine-readable JSON)
+    #[arg(long)]
+    report: Option<ReportOutput>,
+
+    /// write every completed request into a file as `csv=PATH` or `ndjson=PATH`
+    #[arg(long)]
+    events: Option<EventLogOutput>,
+
+    /// serve live metrics (Prometheus text format) at `http://ADDR/metrics` while the load test is running, e.g. `127.0.0.1:9898`
+    #[arg(long)]
+    metrics: Option<String>,
+
+    /// print time series of completed requests, errors and latency in intervals of this length, e.g. `10s` (default: 1s with --timeseries)
+    #[arg(long, value_parser = parse_duration)]
+    interval: Option<Duration>,
+
+    /// also write the time series into this file (CSV)
+    #[arg(long)]
+    timeseries: Option<PathBuf>,
+
+    /// show live dashboard (throughput, in-flight requests, rolling latency and errors) on the terminal while the load test is running
+    #[arg(long)]
+    tui: bool,
+
+    /// record history of all operations and check that the server behaves like a consistent per-table order list
+    #[arg(long)]
+    check_consistency: bool,
+}
+
+#[derive(Args, Debug)]
+struct ReplayArgs {
+    /// workload file recorded by `load --record`
+    file: PathBuf,
+
+    /// load engine: `blocking` (rayon workers) or `async` (tokio). Default is `async` with --timing / --speed, `blocking` otherwise
+    #[arg(long)]
+    engine: Option<LoadEngine>,
+
+    /// start tasks at their recorded time (async engine only). Otherwise, tasks are executed as fast as MAX_THREADS workers can
+    #[arg(long)]
+    timing: bool,
+
+    /// replay the recorded timing this many times faster (implies --timing)
+    #[arg(long)]
+    speed: Option<f64>,
+
+    /// check that 2xx responses are correct for their task kind and report violations separately
+    #[arg(long)]
+    verify: bool,
+}
+
+#[derive(Args, Debug)]
+struct MockServerArgs {
+    /// port to listen on (0 for an ephemeral port)
+    #[arg(short, long, default_value_t = 3333)]
+    port: u16,
+
+    /// latency added to every response, e.g. `20ms`
+    #[arg(long, value_parser = parse_duration, default_value = "0ms")]
+    latency: Duration,
+
+    /// probability (0.0 - 1.0) that a request fails with 500
+    #[arg(long, default_value_t = 0.0)]
+    error_rate: f64,
+
+    /// inject fault as `ROUTE:FAULT:PROBABILITY` (route: ADD, GET_ONE, GET_ALL, REMOVE or `*`;
+    /// fault: server-error, unavailable[=SECS], slow=DURATION, truncate, close or malformed). Can be repeated
+    #[arg(long = "fault")]
+    faults: Vec<FaultRule>,
 }
 
 /// wrapper to execute add command for cli
@@ -54,6 +171,7 @@ fn execute_add_command(
     table_id: i16,
     orders: Vec<String>,
 ) -> Result<String, ClientError> {
+    let client = build_client(config)?;
 
     let mut table_order = TableOrdersRequest::new(table_id);
     
@@ -61,14 +179,15 @@ fn execute_add_command(
         table_order.add_order_wihtout_note(item);
     });
 
-    let task = TaskInfo::new_add_task(table_id, table_order, config);
-    request_add_orders(&task)
+    let task = TaskInfo::new_add_task(table_id, table_order, config, &mut rand::thread_rng());
+    request_add_orders(&client, &task).check_status(&ExpectedStatuses::none()).into_result()
 }
 
 /// wrapper to execute get all orders from specific table command for cli
 fn execute_get_all_command(config: &Config, table_id: i16) -> Result<String, ClientError> {
-    let task = TaskInfo::new_get_all_task(table_id, config);
-    request_get_all_orders( &task)
+    let client = build_client(config)?;
+    let task = TaskInfo::new_get_all_task(table_id, config, &mut rand::thread_rng());
+    request_get_all_orders(&client, &task).check_status(&ExpectedStatuses::none()).into_result()
 }
 
 /// wrapper to execute get one order command for cli
@@ -77,8 +196,9 @@ fn execute_get_one_command(
     table_id: i16,
     order_id: i32,
 ) -> Result<String, ClientError> {
-    let task = TaskInfo::new_get_one_task(table_id, order_id, config);
-    request_get_one_order( &task)
+    let client = build_client(config)?;
+    let task = TaskInfo::new_get_one_task(table_id, order_id, config, &mut rand::thread_rng());
+    request_get_one_order(&client, &task).check_status(&ExpectedStatuses::none()).into_result()
 }
 
 /// wrapper to execute remove order command for cli
@@ -87,12 +207,19 @@ fn execute_remove_command(
     table_id: i16,
     order_id: i32,
 ) -> Result<String, ClientError> {
-    let task = TaskInfo::new_remove_task(table_id, order_id, config);
-    request_remove_order( &task)
+    let client = build_client(config)?;
+    let task = TaskInfo::new_remove_task(table_id, order_id, config, &mut rand::thread_rng());
+    request_remove_order(&client, &task).check_status(&ExpectedStatuses::none()).into_result()
 }
 
 fn main() {
-    tracing_subscriber::fmt().with_thread_names(true).init();
+    let c = Cli::parse();
+    // request logs would garble the dashboard
+    let tui = matches!(&c.command, Operations::Load(args) if args.tui);
+    tracing_subscriber::fmt()
+        .with_thread_names(true)
+        .with_max_level(if tui { tracing::Level::WARN } else { tracing::Level::INFO })
+        .init();
     // load env
     dotenvy::dotenv().ok();
     let mut config: Config =
@@ -101,26 +228,230 @@ fn main() {
     // set delay to 0 because it is unncessary
     config.set_max_delay_time_to_zero();
 
-    let c = Cli::parse();
+    // the blocking client is built only by the commands which use it (async engine builds its own client)
     let results = match c.command {
         Operations::Add { table_id, orders } => execute_add_command(&config, table_id, orders),
         Operations::GetAll { table_id } => execute_get_all_command(&config, table_id),
         Operations::GetOne { table_id, order_id } => execute_get_one_command(&config, table_id, order_id),
         Operations::Remove { table_id, order_id } => execute_remove_command(&config, table_id, order_id),
-        Operations::Load => load_test(&config)
+        Operations::Load(args) => load_test(&config, &args),
+        Operations::Replay(args) => replay(&config, &args),
+        Operations::MockServer(args) => return run_mock_server(&args),
     };
 
     let output = results.unwrap_or_else(|err| format!("Error {err}"));
     println!("{output}");
 }
 
-fn load_test(config: &Config) -> Result<String, ClientError>{
+fn run_mock_server(args: &MockServerArgs) {
+    let options = MockOptions { latency: args.latency, error_rate: args.error_rate, faults: args.faults.clone() };
+    match MockServer::bind(&format!("127.0.0.1:{}", args.port), options) {
+        Ok(server) => {
+            println!("Mock server is listening on {}", server.endpoint());
+            server.wait();
+        }
+        Err(err) => println!("Error {err}"),
+    }
+}
+
+fn load_test(config: &Config, args: &LoadArgs) -> Result<String, ClientError>{
+    let mut config = config.clone();
+    if let Some(seed) = args.seed {
+        config.set_seed(seed);
+    }
+    if let Some(deadline) = args.deadline {
+        config.set_load_deadline(deadline);
+    }
+    let config = &config;
     tracing::info!("{config:?}");
-    let _ = rayon::ThreadPoolBuilder::new()
-        .num_threads(config.get_max_threads() as usize)
-        .build_global();
-    let tasks = gen::gen_all_tasks(&config);
-    let results = execute_tasks(&tasks);
-    let success = results.iter().filter(|x| x.is_ok()).count();
-    Ok(format!("Loading test result -> SUCCESS:{} / FAILED: {}", success, results.len() - success))
+    let expected = config.get_expected_statuses()?;
+    let retry = config.get_retry_policy()?;
+    let profile = config.get_load_profile()?;
+
+    if args.rate.is_some() && profile.is_some() {
+        return Err(ClientError::InvalidConfig("--rate cannot be used together with STAGES".to_string()));
+    }
+    let open_loop = args.rate.is_some() || profile.is_some();
+    // the engine options are validated before observers (dashboard, event log, metrics server) are started
+    let engine = args.engine.unwrap_or(if open_loop { LoadEngine::Async } else { LoadEngine::Blocking });
+    if engine == LoadEngine::Blocking && open_loop {
+        return Err(ClientError::InvalidConfig("open-loop mode (--rate / STAGES) requires async engine".to_string()));
+    }
+    if let Some(rate) = args.rate {
+        engine::check_rate(rate)?;
+    }
+
+    let mut generator = TaskGenerator::new(config)?;
+    let seed = generator.seed();
+    tracing::info!("task generation seed: {seed}");
+    let mut observers = Observers::default();
+    // the metrics server is started before the files are created (the event log and workload are also finished by their `Drop` if a later step fails)
+    let metrics = args.metrics.as_ref()
+        .map(|addr| {
+            let metrics = Arc::new(LoadMetrics::default());
+            MetricsServer::bind(addr, metrics.clone()).map(|server| (metrics, server))
+        })
+        .transpose()?;
+    if let Some((metrics, server)) = &metrics {
+        tracing::info!("serving metrics at http://{}/metrics", server.addr());
+        observers.push(metrics.clone());
+    }
+    let recorder = args.record.as_ref().map(WorkloadRecorder::create).transpose()?.map(Arc::new);
+    if let Some(recorder) = &recorder {
+        observers.push(recorder.clone());
+    }
+    let verifier = args.verify.then(|| Arc::new(ResponseVerifier::default()));
+    if let Some(verifier) = &verifier {
+        observers.push(verifier.clone());
+    }
+    let event_log = args.events.as_ref()
+        .map(|output| EventLog::create(&output.path, output.format))
+        .transpose()?
+        .map(Arc::new);
+    if let Some(event_log) = &event_log {
+        observers.push(event_log.clone());
+    }
+    let dashboard = args.tui.then(|| Arc::new(Dashboard::default()));
+    if let Some(dashboard) = &dashboard {
+        observers.push(dashboard.clone());
+    }
+    let history = args.check_consistency.then(|| Arc::new(HistoryRecorder::default()));
+    if let Some(history) = &history {
+        observers.push(history.clone());
+    }
+    if config.get_stateful_workload() {
+        let tracker = Arc::new(OrderTracker::default());
+        generator = generator.with_tracker(tracker.clone());
+        observers.push(tracker);
+    }
+    let max_tasks = config.get_max_request_tasks().max(0) as usize;
+    let deadline = config.get_load_deadline().map(|deadline| Instant::now() + deadline);
+    let started_at = SystemTime::now();
+    let renderer = dashboard.map(DashboardRenderer::start).transpose()?;
+
+    // the dashboard and files are finished even if the load test fails (e.g., the cursor is restored when the client cannot be built)
+    let results = (|| -> Result<Vec<TaskResult>, ClientError> {
+        let results = match engine {
+            LoadEngine::Blocking => {
+                let _ = rayon::ThreadPoolBuilder::new()
+                    .num_threads(config.get_max_threads() as usize)
+                    .thread_name(|index| format!("worker-{index}"))
+                    .build_global();
+                let client = build_client(config)?;
+                execute_tasks(&client, generator.take(max_tasks), &expected, &retry, deadline, &observers)
+            }
+            LoadEngine::Async => {
+                let client = engine::build_async_client(config)?;
+                let observers = Arc::new(observers);
+                let runtime = async_runtime();
+
+                match (profile, args.rate) {
+                    (Some(profile), _) => {
+                        tracing::info!("duration-based load test for {:?}", profile.total_duration());
+                        runtime.block_on(engine::run_profile(client, &profile, generator, expected, retry, deadline, config.get_max_in_flight(), observers))
+                    }
+                    (None, Some(rate)) => {
+                        runtime.block_on(engine::run_open_loop(client, generator.take(max_tasks), expected, retry, deadline, rate, config.get_max_in_flight(), observers))?
+                    }
+                    (None, None) => {
+                        runtime.block_on(engine::run_closed_loop(client, generator.take(max_tasks), expected, retry, deadline, config.get_max_threads() as usize, observers))
+                    }
+                }
+            }
+        };
+        Ok(results)
+    })();
+
+    if let Some(renderer) = renderer {
+        renderer.finish();
+    }
+    let recorded = recorder.map_or(Ok(()), |recorder| recorder.finish());
+    let logged = event_log.map_or(Ok(()), |event_log| event_log.finish());
+    let results = results?;
+    recorded?;
+    logged?;
+    let report = LoadReport::from_results(&results);
+    let series = match (args.interval, &args.timeseries) {
+        (None, None) => String::new(),
+        (interval, path) => {
+            let interval = interval.unwrap_or(Duration::from_secs(1));
+            let series = TimeSeries::from_results(&results, started_at, interval)?;
+            if let Some(path) = path {
+                series.write_csv(path)?;
+            }
+            format!("\nTime series -> interval {interval:?}\n{series}")
+        }
+    };
+    if let Some(ReportOutput::Json(path)) = &args.report {
+        let run = RunInfo { seed, engine: engine.to_string(), started_at, finished_at: SystemTime::now(), config: config.clone() };
+        report.write_json(&run, path)?;
+    }
+    let consistency = history
+        .map(|history| format!("\n{}", check_history(&history.history())))
+        .unwrap_or_default();
+    Ok(format!("Seed -> {seed}\n{report}{series}{}{consistency}", verification(verifier)))
+}
+
+fn replay(config: &Config, args: &ReplayArgs) -> Result<String, ClientError>{
+    let expected = config.get_expected_statuses()?;
+    let retry = config.get_retry_policy()?;
+    let speed = args.speed.unwrap_or(1.0);
+    if !(speed.is_finite() && speed > 0.0) {
+        return Err(ClientError::InvalidConfig(format!("replay speed must be positive (got {speed})")));
+    }
+    let timing = args.timing || args.speed.is_some();
+
+    let tasks = read_workload(&args.file, config)?;
+    let deadline = config.get_load_deadline().map(|deadline| Instant::now() + deadline);
+    tracing::info!("replaying {} tasks from {}", tasks.len(), args.file.display());
+    let mut observers = Observers::default();
+    let verifier = args.verify.then(|| Arc::new(ResponseVerifier::default()));
+    if let Some(verifier) = &verifier {
+        observers.push(verifier.clone());
+    }
+
+    let results = match args.engine.unwrap_or(if timing { LoadEngine::Async } else { LoadEngine::Blocking }) {
+        LoadEngine::Blocking if timing => {
+            return Err(ClientError::InvalidConfig("replay with recorded timing requires async engine".to_string()))
+        }
+        LoadEngine::Blocking => {
+            let _ = rayon::ThreadPoolBuilder::new()
+                .num_threads(config.get_max_threads() as usize)
+                .thread_name(|index| format!("worker-{index}"))
+                .build_global();
+            let client = build_client(config)?;
+            execute_tasks(&client, tasks.into_iter().map(|(_, task)| task), &expected, &retry, deadline, &observers)
+        }
+        LoadEngine::Async => {
+            let client = engine::build_async_client(config)?;
+            let observers = Arc::new(observers);
+            let runtime = async_runtime();
+
+            if timing {
+                let schedule = tasks.into_iter().map(|(offset, task)| (offset.div_f64(speed), task));
+                runtime.block_on(engine::run_scheduled(client, schedule, expected, retry, deadline, config.get_max_in_flight(), observers))
+            } else {
+                let tasks = tasks.into_iter().map(|(_, task)| task);
+                runtime.block_on(engine::run_closed_loop(client, tasks, expected, retry, deadline, config.get_max_threads() as usize, observers))
+            }
+        }
+    };
+
+    Ok(format!("{}{}", LoadReport::from_results(&results), verification(verifier)))
+}
+
+/// verification report (or nothing if responses were not verified)
+fn verification(verifier: Option<Arc<ResponseVerifier>>) -> String {
+    verifier.map(|verifier| format!("\n{}", verifier.report())).unwrap_or_default()
+}
+
+fn async_runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .thread_name_fn(|| {
+            static WORKER_ID: AtomicUsize = AtomicUsize::new(0);
+            format!("worker-{}", WORKER_ID.fetch_add(1, Ordering::Relaxed))
+        })
+        .build()
+        .expect("Cannot start async runtime (exit now)")
 }
@@ -0,0 +1,197 @@
+use std::{collections::BTreeMap, fmt::Write as _, io::BufReader, net::{SocketAddr, TcpStream}, sync::{atomic::{AtomicI64, Ordering}, Arc, Mutex}, time::Duration};
+
+use crate::{error::{ClientError, ErrorCategory}, gen::{TaskInfo, TaskKind}, http::{self, Listener}, observer::TaskObserver, result::TaskResult};
+
+
+/// upper bounds (seconds) of latency histogram buckets
+const LATENCY_BUCKETS: [f64; 14] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// Metrics of one task kind
+#[derive(Debug, Default)]
+struct KindMetrics{
+    /// number of requests by HTTP status (`None` if no response was received)
+    requests: BTreeMap<Option<u16>, u64>,
+
+    /// number of failed requests by error category
+    errors: BTreeMap<ErrorCategory, u64>,
+
+    retries: u64,
+
+    /// number of requests in each latency bucket (not cumulative)
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    latency_count: u64,
+    latency_sum: f64
+}
+
+
+/// Observer which keeps live metrics of the load test (request counters, in-flight requests and latency histograms)
+#[derive(Debug, Default)]
+pub struct LoadMetrics{
+    in_flight: AtomicI64,
+    kinds: Mutex<BTreeMap<TaskKind, KindMetrics>>
+}
+
+impl LoadMetrics{
+    /// function renders current metrics in Prometheus text format
+    pub fn render(&self) -> String{
+        let kinds = self.kinds.lock().expect("load metrics are poisoned");
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# HELP restaurant_client_requests_total Completed requests by task kind and HTTP status (`none` without response).");
+        let _ = writeln!(out, "# TYPE restaurant_client_requests_total counter");
+        for (kind, metrics) in kinds.iter(){
+            for (status, count) in &metrics.requests{
+                let status = status.map_or("none".to_string(), |status| status.to_string());
+                let _ = writeln!(out, "restaurant_client_requests_total{{kind=\"{}\",status=\"{status}\"}} {count}", kind.name());
+            }
+        }
+
+        let _ = writeln!(out, "# HELP restaurant_client_errors_total Failed requests by task kind and error category.");
+        let _ = writeln!(out, "# TYPE restaurant_client_errors_total counter");
+        for (kind, metrics) in kinds.iter(){
+            for (category, count) in &metrics.errors{
+                let _ = writeln!(out, "restaurant_client_errors_total{{kind=\"{}\",category=\"{category}\"}} {count}", kind.name());
+            }
+        }
+
+        let _ = writeln!(out, "# HELP restaurant_client_retries_total Retries (attempts after the first one) by task kind.");
+        let _ = writeln!(out, "# TYPE restaurant_client_retries_total counter");
+        for (kind, metrics) in kinds.iter(){
+            let _ = writeln!(out, "restaurant_client_retries_total{{kind=\"{}\"}} {}", kind.name(), metrics.retries);
+        }
+
+        let _ = writeln!(out, "# HELP restaurant_client_in_flight_requests Tasks which are started but not completed.");
+        let _ = writeln!(out, "# TYPE restaurant_client_in_flight_requests gauge");
+        let _ = writeln!(out, "restaurant_client_in_flight_requests {}", self.in_flight.load(Ordering::Relaxed));
+
+        let _ = writeln!(out, "# HELP restaurant_client_request_duration_seconds Latency of completed requests by task kind.");
+        let _ = writeln!(out, "# TYPE restaurant_client_request_duration_seconds histogram");
+        for (kind, metrics) in kinds.iter(){
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(metrics.buckets){
+                cumulative += count;
+                let _ = writeln!(out, "restaurant_client_request_duration_seconds_bucket{{kind=\"{}\",le=\"{bound}\"}} {cumulative}", kind.name());
+            }
+            let _ = writeln!(out, "restaurant_client_request_duration_seconds_bucket{{kind=\"{}\",le=\"+Inf\"}} {}", kind.name(), metrics.latency_count);
+            let _ = writeln!(out, "restaurant_client_request_duration_seconds_sum{{kind=\"{}\"}} {}", kind.name(), metrics.latency_sum);
+            let _ = writeln!(out, "restaurant_client_request_duration_seconds_count{{kind=\"{}\"}} {}", kind.name(), metrics.latency_count);
+        }
+        out
+    }
+}
+
+impl TaskObserver for LoadMetrics{
+    fn on_task_start(&self, _task: &TaskInfo){
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_task_complete(&self, _task: &TaskInfo, result: &TaskResult){
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+
+        let mut kinds = self.kinds.lock().expect("load metrics are poisoned");
+        let metrics = kinds.entry(result.kind).or_default();
+        *metrics.requests.entry(result.status).or_insert(0) += 1;
+        if let Some(category) = result.error_category(){
+            *metrics.errors.entry(category).or_insert(0) += 1;
+        }
+        metrics.retries += u64::from(result.attempts.saturating_sub(1));
+
+        let latency = result.latency.as_secs_f64();
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|bound| latency <= *bound){
+            metrics.buckets[bucket] += 1;
+        }
+        metrics.latency_count += 1;
+        metrics.latency_sum += latency;
+    }
+}
+
+
+/// HTTP endpoint which serves `/metrics` (Prometheus text format) while the load test is running. The server is stopped when it is dropped
+pub struct MetricsServer{
+    listener: Listener
+}
+
+impl MetricsServer{
+    /// function starts metrics endpoint on the address (e.g. `127.0.0.1:9898`)
+    pub fn bind(addr: &str, metrics: Arc<LoadMetrics>) -> Result<Self, ClientError>{
+        // scrapes are rare, so connections are served one by one on the accept thread
+        let listener = Listener::bind(addr, "metrics-server", move |stream| {
+            if let Err(err) = serve(stream, &metrics){
+                tracing::debug!("metrics connection closed: {err}");
+            }
+        })?;
+        Ok(Self { listener })
+    }
+
+    pub fn addr(&self) -> SocketAddr{
+        self.listener.addr()
+    }
+}
+
+/// function answers one request and closes the connection
+fn serve(stream: TcpStream, metrics: &LoadMetrics) -> std::io::Result<()>{
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    let Some(request) = http::read_request(&mut reader, &mut writer)? else { return Ok(()) };
+    let (status, body) = match (request.method.as_str(), request.path.split('?').next().unwrap_or_default()){
+        ("GET", "/metrics") => (200, metrics.render()),
+        _ => (404, "not found\n".to_string())
+    };
+    http::write_response(&mut writer, status, &[], "text/plain; version=0.0.4; charset=utf-8", &body, body.len(), false)
+}
+
+
+#[cfg(test)]
+mod test{
+    use std::time::SystemTime;
+
+    use crate::test_support;
+
+    use super::*;
+
+    #[test]
+    fn test_render_metrics(){
+        let task = test_support::task(TaskKind::GetOne);
+        let metrics = LoadMetrics::default();
+
+        metrics.on_task_start(&task);
+        metrics.on_task_start(&task);
+        let mut result = test_support::result(TaskKind::GetOne, 404);
+        result.latency = Duration::from_millis(20);
+        result.attempts = 3;
+        metrics.on_task_complete(&task, &result);
+
+        let text = metrics.render();
+        assert!(text.contains("restaurant_client_requests_total{kind=\"GET_ONE\",status=\"404\"} 1"));
+        assert!(text.contains("restaurant_client_retries_total{kind=\"GET_ONE\"} 2"));
+        assert!(text.contains("restaurant_client_in_flight_requests 1"));
+        assert!(text.contains("restaurant_client_request_duration_seconds_bucket{kind=\"GET_ONE\",le=\"0.01\"} 0"));
+        assert!(text.contains("restaurant_client_request_duration_seconds_bucket{kind=\"GET_ONE\",le=\"0.025\"} 1"));
+        assert!(text.contains("restaurant_client_request_duration_seconds_bucket{kind=\"GET_ONE\",le=\"+Inf\"} 1"));
+        assert!(text.contains("restaurant_client_request_duration_seconds_count{kind=\"GET_ONE\"} 1"));
+
+        let failed = TaskResult::new(&task, SystemTime::now(), Duration::from_secs(60), None, Err(ClientError::DeadlineExceeded));
+        metrics.on_task_complete(&task, &failed);
+        let text = metrics.render();
+        assert!(text.contains("restaurant_client_requests_total{kind=\"GET_ONE\",status=\"none\"} 1"));
+        assert!(text.contains("restaurant_client_errors_total{kind=\"GET_ONE\",category=\"timeout\"} 1"));
+        assert!(text.contains("restaurant_client_request_duration_seconds_bucket{kind=\"GET_ONE\",le=\"30\"} 1"));
+        assert!(text.contains("restaurant_client_request_duration_seconds_bucket{kind=\"GET_ONE\",le=\"+Inf\"} 2"));
+    }
+
+    #[test]
+    fn test_metrics_endpoint(){
+        let metrics = Arc::new(LoadMetrics::default());
+        let server = MetricsServer::bind("127.0.0.1:0", metrics).unwrap();
+        let client = reqwest::blocking::Client::new();
+
+        let response = client.get(format!("http://{}/metrics", server.addr())).send().unwrap();
+        assert_eq!(response.status(), 200);
+        assert!(response.text().unwrap().contains("restaurant_client_in_flight_requests 0"));
+
+        let response = client.get(format!("http://{}/", server.addr())).send().unwrap();
+        assert_eq!(response.status(), 404);
+    }
+}
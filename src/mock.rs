@@ -0,0 +1,462 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    io::BufReader,
+    net::{Shutdown, SocketAddr, TcpStream},
+    sync::{atomic::{AtomicI32, Ordering}, Arc, Mutex},
+    thread,
+    str::FromStr,
+    time::Duration,
+};
+
+use rand::Rng;
+use serde_json::{json, Value};
+
+use crate::{error::ClientError, gen::TaskKind, http::{self, HttpRequest, Listener}, profile::parse_duration};
+
+
+/// content type of mock server responses
+const JSON: &str = "application/json";
+
+
+/// Behaviour knobs of the mock server
+#[derive(Debug, Clone, Default)]
+pub struct MockOptions{
+    /// latency added to every response
+    pub latency: Duration,
+
+    /// probability (0.0 - 1.0) that a request fails with 500 without touching the state
+    pub error_rate: f64,
+
+    /// injected faults. For each request, the first rule which matches the route and fires (by its probability) is applied
+    pub faults: Vec<FaultRule>
+}
+
+
+/// Bad server behaviour injected by the mock server
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fault{
+    /// 500 response (the state is not changed)
+    ServerError,
+
+    /// 503 response with `Retry-After` header in seconds (the state is not changed)
+    Unavailable(u64),
+
+    /// the response is delayed by the duration
+    Slow(Duration),
+
+    /// the request is processed, but the connection is closed in the middle of the response body
+    TruncatedBody,
+
+    /// the connection is closed (gracefully) without response (the state is not changed). It is always closed at the same point:
+    /// after the whole request (headers and body) is read and before the status line is written, so the client sees
+    /// an established connection which ends before the response (request error, not connect error)
+    ConnectionClose,
+
+    /// the request is processed, but the response body is not valid JSON
+    MalformedJson
+}
+
+impl fmt::Display for Fault{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self{
+            Fault::ServerError => write!(f, "server-error"),
+            Fault::Unavailable(retry_after) => write!(f, "unavailable={retry_after}"),
+            Fault::Slow(duration) => write!(f, "slow={}ms", duration.as_millis()),
+            Fault::TruncatedBody => write!(f, "truncate"),
+            Fault::ConnectionClose => write!(f, "close"),
+            Fault::MalformedJson => write!(f, "malformed"),
+        }
+    }
+}
+
+impl FromStr for Fault{
+    type Err = ClientError;
+
+    /// parse fault: `500` / `server-error`, `503[=SECS]` / `unavailable[=SECS]` (default: 1 second), `slow=DURATION`, `truncate`, `close` or `malformed`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ClientError::InvalidConfig(format!("unknown fault `{s}` (expected server-error, unavailable[=SECS], slow=DURATION, truncate, close or malformed)"));
+
+        let (name, value) = match s.trim().split_once('='){
+            Some((name, value)) => (name.trim().to_lowercase(), Some(value.trim())),
+            None => (s.trim().to_lowercase(), None)
+        };
+        match (name.as_str(), value){
+            ("500" | "server-error", None) => Ok(Fault::ServerError),
+            ("503" | "unavailable", None) => Ok(Fault::Unavailable(1)),
+            ("503" | "unavailable", Some(secs)) => secs.parse().map(Fault::Unavailable).map_err(|_| invalid()),
+            ("slow", Some(duration)) => parse_duration(duration).map(Fault::Slow),
+            ("truncate" | "truncated-body", None) => Ok(Fault::TruncatedBody),
+            ("close" | "connection-close", None) => Ok(Fault::ConnectionClose),
+            ("malformed" | "malformed-json", None) => Ok(Fault::MalformedJson),
+            _ => Err(invalid())
+        }
+    }
+}
+
+
+/// Fault injected with `probability` into requests of the route (`None` for all routes)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaultRule{
+    pub route: Option<TaskKind>,
+    pub fault: Fault,
+    pub probability: f64
+}
+
+impl FaultRule{
+    fn matches(&self, route: Option<TaskKind>) -> bool{
+        self.route.is_none() || self.route == route
+    }
+}
+
+impl FromStr for FaultRule{
+    type Err = ClientError;
+
+    /// parse rule: `ROUTE:FAULT:PROBABILITY`, where route is a task kind (`ADD`, `GET_ONE`, `GET_ALL`, `REMOVE`) or `*` for all routes,
+    /// e.g. `GET_ONE:close:0.1` or `*:unavailable=2:0.05`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').map(str::trim).collect();
+        let [route, fault, probability] = parts.as_slice() else {
+            return Err(ClientError::InvalidConfig(format!("fault rule `{s}` is not in ROUTE:FAULT:PROBABILITY form")));
+        };
+
+        let route = match *route{
+            "*" => None,
+            route => Some(route.parse()?)
+        };
+        let probability: f64 = probability.parse().ok()
+            .filter(|probability| (0.0..=1.0).contains(probability))
+            .ok_or_else(|| ClientError::InvalidConfig(format!("fault probability in `{s}` must be between 0 and 1")))?;
+
+        Ok(Self { route, fault: fault.parse()?, probability })
+    }
+}
+
+
+/// In-memory restaurant server for offline tests. It implements add orders, get all orders, get one order and remove order
+/// (`/api/v1/tables/{table_id}/orders[/{order_id}]`). The server is stopped when it is dropped
+pub struct MockServer{
+    listener: Listener
+}
+
+impl MockServer{
+    /// function starts mock server on an ephemeral port of localhost
+    pub fn start(options: MockOptions) -> Result<Self, ClientError>{
+        Self::bind("127.0.0.1:0", options)
+    }
+
+    /// function starts mock server on the address (e.g. `127.0.0.1:3333`)
+    pub fn bind(addr: &str, options: MockOptions) -> Result<Self, ClientError>{
+        if !(0.0..=1.0).contains(&options.error_rate) || options.faults.iter().any(|rule| !(0.0..=1.0).contains(&rule.probability)){
+            return Err(ClientError::InvalidConfig("error rate and fault probabilities must be between 0 and 1".to_string()));
+        }
+
+        let handler = Arc::new(Handler { options, tables: Mutex::default(), next_order_id: AtomicI32::new(1) });
+        let listener = Listener::bind(addr, "mock-server", move |stream| {
+            let handler = handler.clone();
+            thread::spawn(move || {
+                if let Err(err) = handler.serve(stream){
+                    tracing::debug!("mock server connection closed: {err}");
+                }
+            });
+        })?;
+        Ok(Self { listener })
+    }
+
+    pub fn addr(&self) -> SocketAddr{
+        self.listener.addr()
+    }
+
+    /// endpoint url of the server (to be used as `ENDPOINT`)
+    pub fn endpoint(&self) -> String{
+        format!("http://{}", self.addr())
+    }
+
+    /// function blocks current thread while the server is running
+    pub fn wait(self){
+        self.listener.wait()
+    }
+}
+
+/// HTTP response (status and JSON body)
+struct HttpResponse{
+    status: u16,
+    body: Value
+}
+
+impl HttpResponse{
+    fn error(status: u16, message: &str) -> Self{
+        Self { status, body: json!({ "error": message }) }
+    }
+}
+
+/// Order operation requested by the client
+struct Route{
+    kind: TaskKind,
+    table_id: i16,
+    order_id: Option<i32>
+}
+
+/// function parses method and path of the request into order operation
+fn parse_route(request: &HttpRequest) -> Result<Route, HttpResponse>{
+    let path = request.path.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    let (table_id, order_id) = match segments.as_slice(){
+        ["api", "v1", "tables", table_id, "orders"] => (*table_id, None),
+        ["api", "v1", "tables", table_id, "orders", order_id] => match order_id.parse::<i32>(){
+            Ok(order_id) => (*table_id, Some(order_id)),
+            Err(_) => return Err(HttpResponse::error(400, "invalid order id"))
+        },
+        _ => return Err(HttpResponse::error(404, "not found"))
+    };
+    let table_id = table_id.parse::<i16>().map_err(|_| HttpResponse::error(400, "invalid table id"))?;
+
+    let kind = match (request.method.as_str(), order_id){
+        ("POST", None) => TaskKind::Add,
+        ("GET", None) => TaskKind::GetAll,
+        ("GET", Some(_)) => TaskKind::GetOne,
+        ("DELETE", Some(_)) => TaskKind::Remove,
+        _ => return Err(HttpResponse::error(405, "method not allowed"))
+    };
+    Ok(Route { kind, table_id, order_id })
+}
+
+/// utility function to cut string in half (at char boundary)
+fn half(body: &str) -> &str{
+    let mut end = body.len() / 2;
+    while !body.is_char_boundary(end){
+        end -= 1;
+    }
+    &body[..end]
+}
+
+
+/// Request handler and in-memory state of the mock server
+struct Handler{
+    options: MockOptions,
+
+    /// orders (JSON objects) of each table by order id
+    tables: Mutex<HashMap<i16, BTreeMap<i32, Value>>>,
+
+    next_order_id: AtomicI32
+}
+
+impl Handler{
+    /// function serves requests of one (keep-alive) connection
+    fn serve(&self, stream: TcpStream) -> std::io::Result<()>{
+        // idle keep-alive connections are closed, so the threads do not outlive the server forever
+        stream.set_read_timeout(Some(Duration::from_secs(30)))?;
+        stream.set_nodelay(true)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+
+        while let Some(request) = http::read_request(&mut reader, &mut writer)?{
+            thread::sleep(self.options.latency);
+            let route = parse_route(&request);
+            let keep_alive = request.keep_alive;
+
+            match self.select_fault(route.as_ref().ok().map(|route| route.kind)){
+                // both directions are shut down here (dropping the stream would close it only after the reader clone is dropped too)
+                Some(Fault::ConnectionClose) => return writer.shutdown(Shutdown::Both),
+                Some(Fault::ServerError) => {
+                    let body = HttpResponse::error(500, "injected error").body.to_string();
+                    http::write_response(&mut writer, 500, &[], JSON, &body, body.len(), keep_alive)?;
+                },
+                Some(Fault::Unavailable(retry_after)) => {
+                    let body = HttpResponse::error(503, "injected unavailability").body.to_string();
+                    http::write_response(&mut writer, 503, &[("Retry-After", retry_after.to_string())], JSON, &body, body.len(), keep_alive)?;
+                },
+                Some(Fault::TruncatedBody) => {
+                    let response = self.handle(route, &request.body);
+                    let body = response.body.to_string();
+                    http::write_response(&mut writer, response.status, &[], JSON, half(&body), body.len(), false)?;
+                    return Ok(());
+                },
+                Some(Fault::MalformedJson) => {
+                    let response = self.handle(route, &request.body);
+                    let body = response.body.to_string();
+                    let body = half(&body);
+                    http::write_response(&mut writer, response.status, &[], JSON, body, body.len(), keep_alive)?;
+                },
+                fault => {
+                    if let Some(Fault::Slow(duration)) = fault{
+                        thread::sleep(duration);
+                    }
+                    let response = self.handle(route, &request.body);
+                    let body = response.body.to_string();
+                    http::write_response(&mut writer, response.status, &[], JSON, &body, body.len(), keep_alive)?;
+                }
+            }
+
+            if !keep_alive{
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// function selects fault injected into the request of the route (if any)
+    fn select_fault(&self, route: Option<TaskKind>) -> Option<Fault>{
+        let mut rng = rand::thread_rng();
+        self.options.faults.iter()
+            .find(|rule| rule.matches(route) && rng.gen_bool(rule.probability))
+            .map(|rule| rule.fault.clone())
+            .or_else(|| rng.gen_bool(self.options.error_rate).then_some(Fault::ServerError))
+    }
+
+    /// function executes the order operation
+    fn handle(&self, route: Result<Route, HttpResponse>, body: &[u8]) -> HttpResponse{
+        let route = match route{
+            Ok(route) => route,
+            Err(response) => return response
+        };
+
+        match (route.kind, route.order_id){
+            (TaskKind::Add, _) => self.add_orders(route.table_id, body),
+            (TaskKind::GetAll, _) => self.get_all_orders(route.table_id),
+            (TaskKind::GetOne, Some(order_id)) => self.get_one_order(route.table_id, order_id),
+            (TaskKind::Remove, Some(order_id)) => self.remove_order(route.table_id, order_id),
+            (TaskKind::GetOne | TaskKind::Remove, None) => HttpResponse::error(405, "method not allowed")
+        }
+    }
+
+    fn table_response(table_id: i16, orders: Vec<Value>) -> Value{
+        json!({ "table_id": table_id, "orders": orders })
+    }
+
+    /// add orders of the request body (`TableOrdersRequest`). The fields of each order are kept and `order_id` / `table_id` are assigned
+    fn add_orders(&self, table_id: i16, body: &[u8]) -> HttpResponse{
+        let Some(orders) = serde_json::from_slice::<Value>(body).ok().and_then(|json| json.pointer("/orders").and_then(Value::as_array).cloned()) else {
+            return HttpResponse::error(400, "invalid table orders request");
+        };
+        if orders.is_empty() || orders.iter().any(|order| !order.is_object()){
+            return HttpResponse::error(400, "invalid table orders request");
+        }
+
+        let mut tables = self.tables.lock().expect("mock server state is poisoned");
+        let table = tables.entry(table_id).or_default();
+        let added: Vec<Value> = orders.into_iter()
+            .map(|mut order| {
+                let order_id = self.next_order_id.fetch_add(1, Ordering::SeqCst);
+                order["order_id"] = json!(order_id);
+                order["table_id"] = json!(table_id);
+                table.insert(order_id, order.clone());
+                order
+            })
+            .collect();
+
+        HttpResponse { status: 201, body: Self::table_response(table_id, added) }
+    }
+
+    fn get_all_orders(&self, table_id: i16) -> HttpResponse{
+        let tables = self.tables.lock().expect("mock server state is poisoned");
+        let orders = tables.get(&table_id).map(|table| table.values().cloned().collect()).unwrap_or_default();
+        HttpResponse { status: 200, body: Self::table_response(table_id, orders) }
+    }
+
+    fn get_one_order(&self, table_id: i16, order_id: i32) -> HttpResponse{
+        let tables = self.tables.lock().expect("mock server state is poisoned");
+        match tables.get(&table_id).and_then(|table| table.get(&order_id)){
+            Some(order) => HttpResponse { status: 200, body: Self::table_response(table_id, vec![order.clone()]) },
+            None => HttpResponse::error(404, "order not found")
+        }
+    }
+
+    fn remove_order(&self, table_id: i16, order_id: i32) -> HttpResponse{
+        let mut tables = self.tables.lock().expect("mock server state is poisoned");
+        match tables.get_mut(&table_id).and_then(|table| table.remove(&order_id)){
+            Some(order) => HttpResponse { status: 200, body: Self::table_response(table_id, vec![order]) },
+            None => HttpResponse::error(404, "order not found")
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test{
+    use std::time::Instant;
+
+    use super::*;
+
+    fn request(client: &reqwest::blocking::Client, method: reqwest::Method, url: &str, body: Option<&str>) -> (u16, Value){
+        let mut request = client.request(method, url);
+        if let Some(body) = body{
+            request = request.header("Content-Type", "application/json").body(body.to_string());
+        }
+        let response = request.send().unwrap();
+        (response.status().as_u16(), serde_json::from_str(&response.text().unwrap()).unwrap())
+    }
+
+    #[test]
+    fn test_order_operations(){
+        let server = MockServer::start(MockOptions::default()).unwrap();
+        let client = reqwest::blocking::Client::new();
+        let orders_url = format!("{}/api/v1/tables/2/orders", server.endpoint());
+
+        let (status, added) = request(&client, reqwest::Method::POST, &orders_url, Some(r#"{"table_id":2,"orders":[{"item_name":"a"},{"item_name":"b"}]}"#));
+        assert_eq!(status, 201);
+        assert_eq!(added["table_id"], 2);
+        assert_eq!(added["orders"][1]["item_name"], "b");
+        let order_id = added["orders"][0]["order_id"].as_i64().unwrap();
+
+        let (status, all) = request(&client, reqwest::Method::GET, &orders_url, None);
+        assert_eq!(status, 200);
+        assert_eq!(all["orders"].as_array().unwrap().len(), 2);
+
+        let order_url = format!("{orders_url}/{order_id}");
+        let (status, one) = request(&client, reqwest::Method::GET, &order_url, None);
+        assert_eq!((status, one["orders"][0]["item_name"].as_str()), (200, Some("a")));
+
+        assert_eq!(request(&client, reqwest::Method::DELETE, &order_url, None).0, 200);
+        assert_eq!(request(&client, reqwest::Method::DELETE, &order_url, None).0, 404);
+        assert_eq!(request(&client, reqwest::Method::GET, &order_url, None).0, 404);
+
+        // other tables are empty
+        let (_, other) = request(&client, reqwest::Method::GET, &format!("{}/api/v1/tables/3/orders", server.endpoint()), None);
+        assert!(other["orders"].as_array().unwrap().is_empty());
+
+        assert_eq!(request(&client, reqwest::Method::POST, &orders_url, Some("not json")).0, 400);
+        assert_eq!(request(&client, reqwest::Method::GET, &format!("{}/api/v1/menu", server.endpoint()), None).0, 404);
+    }
+
+    #[test]
+    fn test_latency_and_errors(){
+        let server = MockServer::start(MockOptions { latency: Duration::from_millis(50), error_rate: 1.0, ..Default::default() }).unwrap();
+        let client = reqwest::blocking::Client::new();
+
+        let start = Instant::now();
+        let (status, _) = request(&client, reqwest::Method::GET, &format!("{}/api/v1/tables/1/orders", server.endpoint()), None);
+        assert_eq!(status, 500);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        assert!(MockServer::start(MockOptions { error_rate: 2.0, ..Default::default() }).is_err());
+    }
+
+    #[test]
+    fn test_parse_fault_rule(){
+        assert_eq!("GET_ONE:close:0.1".parse::<FaultRule>().unwrap(), FaultRule { route: Some(TaskKind::GetOne), fault: Fault::ConnectionClose, probability: 0.1 });
+        assert_eq!("*:unavailable=2:1".parse::<FaultRule>().unwrap(), FaultRule { route: None, fault: Fault::Unavailable(2), probability: 1.0 });
+        assert_eq!("add:slow=500ms:0.5".parse::<FaultRule>().unwrap().fault, Fault::Slow(Duration::from_millis(500)));
+        assert_eq!("*:503:1".parse::<FaultRule>().unwrap().fault, Fault::Unavailable(1));
+        assert!("*:close".parse::<FaultRule>().is_err());
+        assert!("*:close:2".parse::<FaultRule>().is_err());
+        assert!("UPDATE:close:1".parse::<FaultRule>().is_err());
+        assert!("*:explode:1".parse::<FaultRule>().is_err());
+    }
+
+    #[test]
+    fn test_fault_per_route(){
+        let faults = vec!["GET_ALL:503:1".parse().unwrap()];
+        let server = MockServer::start(MockOptions { faults, ..Default::default() }).unwrap();
+        let client = reqwest::blocking::Client::new();
+        let url = format!("{}/api/v1/tables/1/orders", server.endpoint());
+
+        let response = client.get(&url).send().unwrap();
+        assert_eq!(response.status().as_u16(), 503);
+        assert_eq!(response.headers()["retry-after"], "1");
+
+        // other routes are not affected
+        assert_eq!(request(&client, reqwest::Method::GET, &format!("{url}/1"), None).0, 404);
+    }
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use crate::{gen::TaskInfo, result::TaskResult};
+
+
+/// Hook which is notified about every task while the load test is running (e.g., to track created orders)
+pub trait TaskObserver: Send + Sync{
+    /// called before the request of the task is sent
+    fn on_task_start(&self, _task: &TaskInfo){}
+
+    /// called when the task is completed
+    fn on_task_complete(&self, _task: &TaskInfo, _result: &TaskResult){}
+}
+
+
+/// Observer which forwards notifications to all registered observers. Without observers, it ignores everything
+#[derive(Default, Clone)]
+pub struct Observers(Vec<Arc<dyn TaskObserver>>);
+
+impl Observers{
+    /// function registers observer
+    pub fn push(&mut self, observer: Arc<dyn TaskObserver>){
+        self.0.push(observer)
+    }
+}
+
+impl TaskObserver for Observers{
+    fn on_task_start(&self, task: &TaskInfo){
+        self.0.iter().for_each(|observer| observer.on_task_start(task))
+    }
+
+    fn on_task_complete(&self, task: &TaskInfo, result: &TaskResult){
+        self.0.iter().for_each(|observer| observer.on_task_complete(task, result))
+    }
+}
@@ -0,0 +1,220 @@
+use std::{str::FromStr, time::Duration};
+
+use crate::error::ClientError;
+
+
+/// utility function to parse duration from config. Supported units are `ms`, `s`, `m` and `h` (seconds if no unit)
+pub fn parse_duration(s: &str) -> Result<Duration, ClientError>{
+    let s = s.trim();
+    let split = s.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+
+    let value: f64 = value.parse()
+        .ok()
+        .filter(|value: &f64| value.is_finite() && *value >= 0.0)
+        .ok_or_else(|| ClientError::InvalidConfig(format!("invalid duration `{s}`")))?;
+
+    let secs = match unit.trim(){
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        _ => return Err(ClientError::InvalidConfig(format!("invalid duration unit in `{s}` (expected ms, s, m or h)")))
+    };
+    Duration::try_from_secs_f64(secs).map_err(|_| ClientError::InvalidConfig(format!("duration `{s}` is too long")))
+}
+
+
+/// One stage of load profile. The request rate changes linearly from `start_rate` to `end_rate` during the stage
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stage{
+    /// request rate (requests/sec) at the beginning of the stage
+    pub start_rate: f64,
+
+    /// request rate (requests/sec) at the end of the stage
+    pub end_rate: f64,
+
+    pub duration: Duration
+}
+
+impl Stage{
+    /// number of requests issued during the whole stage
+    fn expected_requests(&self) -> f64{
+        (self.start_rate + self.end_rate) / 2.0 * self.duration.as_secs_f64()
+    }
+
+    /// time (seconds from the beginning of the stage) when `n` requests have been issued
+    fn offset_of(&self, n: f64) -> f64{
+        let duration = self.duration.as_secs_f64();
+        let slope = (self.end_rate - self.start_rate) / duration;
+
+        // solve start_rate * t + slope * t^2 / 2 = n
+        let offset = if slope.abs() < 1e-9{
+            n / self.start_rate
+        }else{
+            (-self.start_rate + (self.start_rate.powi(2) + 2.0 * slope * n).max(0.0).sqrt()) / slope
+        };
+        offset.clamp(0.0, duration)
+    }
+}
+
+impl FromStr for Stage{
+    type Err = ClientError;
+
+    /// parse stage from config, e.g. `10-200:60s` (ramp from 10 to 200 rps in 60 seconds) or `200:5m` (hold 200 rps for 5 minutes)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ClientError::InvalidConfig(format!("invalid stage `{s}` (expected RATE[-RATE]:DURATION, e.g. 10-200:60s)"));
+        let parse_rate = |rate: &str| rate.trim().parse::<f64>().ok().filter(|rate| rate.is_finite() && *rate >= 0.0).ok_or_else(invalid);
+
+        let (rates, duration) = s.split_once(':').ok_or_else(invalid)?;
+        let (start_rate, end_rate) = match rates.split_once('-'){
+            Some((start, end)) => (parse_rate(start)?, parse_rate(end)?),
+            None => (parse_rate(rates)?, parse_rate(rates)?)
+        };
+
+        let duration = parse_duration(duration)?;
+        if duration.is_zero(){
+            return Err(invalid());
+        }
+        Ok(Self { start_rate, end_rate, duration })
+    }
+}
+
+
+/// Load profile of duration-based load test (sequence of stages)
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadProfile{
+    stages: Vec<Stage>
+}
+
+impl LoadProfile{
+    pub fn stages(&self) -> &[Stage]{
+        &self.stages
+    }
+
+    /// total wall-clock duration of the profile
+    pub fn total_duration(&self) -> Duration{
+        self.stages.iter().map(|stage| stage.duration).sum()
+    }
+
+    /// function returns iterator of request start times (offset from the beginning of the test)
+    pub fn arrivals(&self) -> Arrivals<'_>{
+        Arrivals { stages: &self.stages, index: 0, stage_offset: 0.0, issued_before_stage: 0.0, next: 0 }
+    }
+}
+
+impl FromStr for LoadProfile{
+    type Err = ClientError;
+
+    /// parse profile from config, e.g. `10-200:60s,200:5m,200-0:30s`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let stages = s.split(',')
+            .map(str::trim)
+            .filter(|stage| !stage.is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<Stage>, ClientError>>()?;
+
+        if stages.is_empty(){
+            return Err(ClientError::InvalidConfig("load profile has no stage".to_string()));
+        }
+        Ok(Self { stages })
+    }
+}
+
+
+/// Iterator of request start times of load profile
+pub struct Arrivals<'a>{
+    stages: &'a [Stage],
+
+    /// index of current stage
+    index: usize,
+
+    /// start time (seconds) of current stage
+    stage_offset: f64,
+
+    /// number of requests issued in previous stages (fractional)
+    issued_before_stage: f64,
+
+    /// index of next request
+    next: u64
+}
+
+impl Iterator for Arrivals<'_>{
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop{
+            let stage = self.stages.get(self.index)?;
+            let n = self.next as f64 - self.issued_before_stage;
+
+            if n < stage.expected_requests(){
+                self.next += 1;
+                return Some(Duration::from_secs_f64(self.stage_offset + stage.offset_of(n)));
+            }
+
+            self.issued_before_stage += stage.expected_requests();
+            self.stage_offset += stage.duration.as_secs_f64();
+            self.index += 1;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_parse_duration(){
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("30").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+        assert!(parse_duration("abc").is_err());
+        assert!(parse_duration("10d").is_err());
+        assert!(parse_duration("99999999999999999999999h").is_err());
+        assert!(parse_duration(&"9".repeat(400)).is_err());
+    }
+
+    #[test]
+    fn test_parse_profile(){
+        let profile: LoadProfile = "10-200:60s, 200:5m,200-0:30s".parse().unwrap();
+
+        assert_eq!(profile.stages().len(), 3);
+        assert_eq!(profile.stages()[0], Stage { start_rate: 10.0, end_rate: 200.0, duration: Duration::from_secs(60) });
+        assert_eq!(profile.stages()[1], Stage { start_rate: 200.0, end_rate: 200.0, duration: Duration::from_secs(300) });
+        assert_eq!(profile.total_duration(), Duration::from_secs(390));
+
+        assert!("".parse::<LoadProfile>().is_err());
+        assert!("10".parse::<LoadProfile>().is_err());
+        assert!("10:0s".parse::<LoadProfile>().is_err());
+        assert!("a-10:5s".parse::<LoadProfile>().is_err());
+    }
+
+    #[test]
+    fn test_constant_arrivals(){
+        let profile: LoadProfile = "10:2s".parse().unwrap();
+        let arrivals: Vec<Duration> = profile.arrivals().collect();
+
+        assert_eq!(arrivals.len(), 20);
+        assert_eq!(arrivals[0], Duration::ZERO);
+        assert_eq!(arrivals[1].as_millis(), 100);
+        assert_eq!(arrivals[19].as_millis(), 1900);
+    }
+
+    #[test]
+    fn test_ramp_arrivals(){
+        let profile: LoadProfile = "0-10:2s,10-0:2s".parse().unwrap();
+        let arrivals: Vec<Duration> = profile.arrivals().collect();
+
+        // 10 requests for each ramp
+        assert_eq!(arrivals.len(), 20);
+        assert!(arrivals.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(*arrivals.last().unwrap() <= profile.total_duration());
+
+        // ramp up: requests are denser at the end of the stage
+        let first_second = arrivals.iter().filter(|time| time.as_secs_f64() < 1.0).count();
+        assert!(first_second < 5);
+    }
+}
@@ -0,0 +1,519 @@
+use std::{collections::BTreeMap, fmt, fs::File, io::{BufWriter, Write}, path::{Path, PathBuf}, str::FromStr, time::{Duration, SystemTime}};
+
+use serde::Serialize;
+
+use crate::{config::Config, error::{ClientError, ErrorCategory}, gen::TaskKind, result::TaskResult};
+
+
+/// Latency statistics of a group of requests
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencySummary{
+    /// number of samples
+    pub count: usize,
+
+    pub min: Duration,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub p999: Duration,
+    pub max: Duration
+}
+
+impl LatencySummary{
+    /// function computes summary from latency samples. `None` is returned if there is no sample
+    pub fn from_latencies(latencies: &[Duration]) -> Option<Self>{
+        if latencies.is_empty(){
+            return None;
+        }
+
+        let mut sorted = latencies.to_vec();
+        sorted.sort_unstable();
+
+        let total: Duration = sorted.iter().sum();
+        Some(Self {
+            count: sorted.len(),
+            min: sorted[0],
+            mean: total / sorted.len() as u32,
+            p50: percentile(&sorted, 50.0),
+            p90: percentile(&sorted, 90.0),
+            p99: percentile(&sorted, 99.0),
+            p999: percentile(&sorted, 99.9),
+            max: sorted[sorted.len() - 1],
+        })
+    }
+}
+
+
+/// nearest-rank percentile of sorted (non-empty) samples
+pub fn percentile(sorted: &[Duration], p: f64) -> Duration{
+    // small epsilon avoids floating point error pushing the rank up (e.g. 99.9% of 1000 samples)
+    let rank = (p * sorted.len() as f64 / 100.0 - 1e-9).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+
+/// Request counts of one task kind
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KindCounts{
+    pub success: usize,
+    pub failed: usize,
+
+    /// number of responses for each HTTP status
+    pub statuses: BTreeMap<u16, usize>,
+
+    /// number of requests failed without unexpected HTTP status for each error category
+    pub errors: BTreeMap<ErrorCategory, usize>
+}
+
+
+/// Final report of the load test
+#[derive(Debug)]
+pub struct LoadReport{
+    pub success: usize,
+    pub failed: usize,
+
+    /// latency of all requests
+    pub overall: Option<LatencySummary>,
+
+    /// latency of requests for each task kind (kinds without requests are omitted)
+    pub per_kind: Vec<(TaskKind, LatencySummary)>,
+
+    /// request counts for each task kind (kinds without requests are omitted)
+    pub per_kind_counts: BTreeMap<TaskKind, KindCounts>,
+
+    /// number of succeeded requests with acceptable non-2xx status (e.g., 404 for random order id) for each status
+    pub expected_statuses: BTreeMap<u16, usize>,
+
+    /// number of failed requests with unexpected HTTP status for each status
+    pub unexpected_statuses: BTreeMap<u16, usize>,
+
+    /// number of requests failed without unexpected HTTP status (e.g., transport errors) for each error category
+    pub errors: BTreeMap<ErrorCategory, usize>,
+
+    /// number of requests for each table
+    pub per_table: BTreeMap<i16, usize>,
+
+    /// number of retries (attempts after the first one) of all requests
+    pub retries: usize,
+
+    /// number of requests which were retried
+    pub retried: usize,
+
+    /// number of retried requests which succeeded in the end
+    pub recovered: usize
+}
+
+impl LoadReport{
+    /// function builds report from the results of `execute_tasks`
+    pub fn from_results(results: &[TaskResult]) -> Self{
+        let success = results.iter().filter(|x| x.is_success()).count();
+        let latencies: Vec<Duration> = results.iter().map(|x| x.latency).collect();
+
+        let per_kind = TaskKind::ALL.iter()
+            .filter_map(|kind| {
+                let latencies: Vec<Duration> = results.iter()
+                    .filter(|x| x.kind == *kind)
+                    .map(|x| x.latency)
+                    .collect();
+                LatencySummary::from_latencies(&latencies).map(|summary| (*kind, summary))
+            })
+            .collect();
+
+        let mut expected_statuses = BTreeMap::new();
+        let mut unexpected_statuses = BTreeMap::new();
+        let mut errors = BTreeMap::new();
+        let mut per_table = BTreeMap::new();
+        let mut per_kind_counts: BTreeMap<TaskKind, KindCounts> = BTreeMap::new();
+        let (mut retries, mut retried, mut recovered) = (0, 0, 0);
+        for result in results{
+            *per_table.entry(result.table_id).or_insert(0) += 1;
+
+            let counts = per_kind_counts.entry(result.kind).or_default();
+            if result.is_success() { counts.success += 1 } else { counts.failed += 1 }
+            if let Some(status) = result.status{
+                *counts.statuses.entry(status).or_insert(0) += 1;
+            }
+
+            if result.is_retried(){
+                retries += result.attempts as usize - 1;
+                retried += 1;
+                recovered += result.is_success() as usize;
+            }
+
+            match (result.error_category(), result.status){
+                (None, Some(status)) if result.is_expected_non_2xx() => *expected_statuses.entry(status).or_insert(0) += 1,
+                (Some(ErrorCategory::Http4xx | ErrorCategory::Http5xx | ErrorCategory::HttpOther), Some(status)) => *unexpected_statuses.entry(status).or_insert(0) += 1,
+                (Some(category), _) => {
+                    *errors.entry(category).or_insert(0) += 1;
+                    *counts.errors.entry(category).or_insert(0) += 1;
+                },
+                _ => {}
+            }
+        }
+
+        Self {
+            success,
+            failed: results.len() - success,
+            overall: LatencySummary::from_latencies(&latencies),
+            per_kind,
+            per_kind_counts,
+            expected_statuses,
+            unexpected_statuses,
+            errors,
+            per_table,
+            retries,
+            retried,
+            recovered
+        }
+    }
+
+    /// function writes the report with metadata of the run as JSON into the file
+    pub fn write_json(&self, run: &RunInfo, path: &Path) -> Result<(), ClientError>{
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, &JsonReport::new(self, run))
+            .map_err(|err| ClientError::SerializationError(Box::new(err)))?;
+        writeln!(writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+
+/// Output of the report in addition to stdout (e.g., `json=report.json`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReportOutput{
+    /// machine-readable JSON report written into the file
+    Json(PathBuf)
+}
+
+impl FromStr for ReportOutput{
+    type Err = ClientError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('='){
+            Some((format, path)) if format.trim().eq_ignore_ascii_case("json") && !path.trim().is_empty() => Ok(ReportOutput::Json(PathBuf::from(path.trim()))),
+            _ => Err(ClientError::InvalidConfig(format!("invalid report output `{s}` (expected json=PATH)")))
+        }
+    }
+}
+
+
+/// version of the JSON report schema. Increase it when fields are changed or removed
+pub const REPORT_SCHEMA_VERSION: u32 = 1;
+
+/// Metadata of the load test run in the JSON report
+#[derive(Debug, Serialize)]
+pub struct RunInfo{
+    /// seed of random task generation (as string, since JSON numbers lose precision above 2^53 in many parsers)
+    #[serde(serialize_with = "as_string")]
+    pub seed: u64,
+
+    /// load engine (`blocking` or `async`)
+    pub engine: String,
+
+    /// time when the load test was started / finished
+    #[serde(rename = "started_at_unix_ms", serialize_with = "unix_millis")]
+    pub started_at: SystemTime,
+    #[serde(rename = "finished_at_unix_ms", serialize_with = "unix_millis")]
+    pub finished_at: SystemTime,
+
+    /// configuration of the load test (after command line overrides, unset parameters are `null`)
+    pub config: Config
+}
+
+fn as_string<S: serde::Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error>{
+    serializer.collect_str(value)
+}
+
+fn unix_millis<S: serde::Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error>{
+    let millis = time.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+    serializer.serialize_u64(millis)
+}
+
+/// Latency statistics in milliseconds
+#[derive(Debug, Serialize)]
+struct JsonLatency{
+    min: f64,
+    mean: f64,
+    p50: f64,
+    p90: f64,
+    p99: f64,
+    p999: f64,
+    max: f64
+}
+
+impl From<&LatencySummary> for JsonLatency{
+    fn from(s: &LatencySummary) -> Self {
+        Self {
+            min: as_millis(s.min), mean: as_millis(s.mean), p50: as_millis(s.p50), p90: as_millis(s.p90),
+            p99: as_millis(s.p99), p999: as_millis(s.p999), max: as_millis(s.max)
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct JsonTotals{
+    requests: usize,
+    success: usize,
+    failed: usize,
+    retries: usize,
+    retried: usize,
+    recovered: usize,
+    duration_ms: f64,
+    throughput_per_sec: f64
+}
+
+#[derive(Debug, Serialize)]
+struct JsonKind<'a>{
+    kind: TaskKind,
+    requests: usize,
+    success: usize,
+    failed: usize,
+    latency_ms: Option<JsonLatency>,
+    statuses: &'a BTreeMap<u16, usize>,
+    errors: BTreeMap<String, usize>
+}
+
+/// Structure of the JSON report
+#[derive(Debug, Serialize)]
+struct JsonReport<'a>{
+    schema_version: u32,
+    run: &'a RunInfo,
+    totals: JsonTotals,
+    latency_ms: Option<JsonLatency>,
+    per_kind: Vec<JsonKind<'a>>,
+    expected_statuses: &'a BTreeMap<u16, usize>,
+    unexpected_statuses: &'a BTreeMap<u16, usize>,
+    errors: BTreeMap<String, usize>,
+    per_table: &'a BTreeMap<i16, usize>
+}
+
+/// error counts keyed by category name (same as the text report)
+fn error_names(errors: &BTreeMap<ErrorCategory, usize>) -> BTreeMap<String, usize>{
+    errors.iter().map(|(category, count)| (category.to_string(), *count)).collect()
+}
+
+impl<'a> JsonReport<'a>{
+    fn new(report: &'a LoadReport, run: &'a RunInfo) -> Self{
+        let requests = report.success + report.failed;
+        let duration = run.finished_at.duration_since(run.started_at).unwrap_or_default();
+        let throughput_per_sec = if duration.is_zero() { 0.0 } else { requests as f64 / duration.as_secs_f64() };
+
+        let per_kind = report.per_kind_counts.iter()
+            .map(|(kind, counts)| JsonKind {
+                kind: *kind,
+                requests: counts.success + counts.failed,
+                success: counts.success,
+                failed: counts.failed,
+                latency_ms: report.per_kind.iter().find(|(x, _)| x == kind).map(|(_, summary)| summary.into()),
+                statuses: &counts.statuses,
+                errors: error_names(&counts.errors)
+            })
+            .collect();
+
+        Self {
+            schema_version: REPORT_SCHEMA_VERSION,
+            run,
+            totals: JsonTotals {
+                requests,
+                success: report.success,
+                failed: report.failed,
+                retries: report.retries,
+                retried: report.retried,
+                recovered: report.recovered,
+                duration_ms: as_millis(duration),
+                throughput_per_sec
+            },
+            latency_ms: report.overall.as_ref().map(JsonLatency::from),
+            per_kind,
+            expected_statuses: &report.expected_statuses,
+            unexpected_statuses: &report.unexpected_statuses,
+            errors: error_names(&report.errors),
+            per_table: &report.per_table
+        }
+    }
+}
+
+
+fn as_millis(duration: Duration) -> f64{
+    duration.as_secs_f64() * 1000.0
+}
+
+fn write_counts<K: fmt::Display>(f: &mut fmt::Formatter<'_>, title: &str, counts: &BTreeMap<K, usize>) -> fmt::Result{
+    if counts.is_empty(){
+        return Ok(());
+    }
+    let counts: Vec<String> = counts.iter().map(|(key, count)| format!("{key}={count}")).collect();
+    writeln!(f, "{title} -> {}", counts.join(" "))
+}
+
+/// number of tables shown in the report (the hottest first)
+const REPORTED_TABLES: usize = 10;
+
+fn write_table_load(f: &mut fmt::Formatter<'_>, per_table: &BTreeMap<i16, usize>, total: usize) -> fmt::Result{
+    let mut hottest: Vec<(&i16, &usize)> = per_table.iter().collect();
+    hottest.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+
+    let counts: Vec<String> = hottest.iter()
+        .take(REPORTED_TABLES)
+        .map(|(table_id, count)| format!("{table_id}={count} ({:.1}%)", **count as f64 * 100.0 / total as f64))
+        .collect();
+    let rest = if per_table.len() > REPORTED_TABLES { format!(" ... ({} tables)", per_table.len()) } else { String::new() };
+    writeln!(f, "Table load -> {}{rest}", counts.join(" "))
+}
+
+fn write_latency_row(f: &mut fmt::Formatter<'_>, name: &dyn fmt::Display, s: &LatencySummary) -> fmt::Result{
+    writeln!(f, "{:<8} {:>8} {:>10.3} {:>10.3} {:>10.3} {:>10.3} {:>10.3} {:>10.3} {:>10.3}",
+        name.to_string(), s.count, as_millis(s.min), as_millis(s.mean), as_millis(s.p50),
+        as_millis(s.p90), as_millis(s.p99), as_millis(s.p999), as_millis(s.max))
+}
+
+impl fmt::Display for LoadReport{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Loading test result -> SUCCESS:{} / FAILED: {}", self.success, self.failed)?;
+        write_counts(f, "Expected non-2xx statuses", &self.expected_statuses)?;
+        write_counts(f, "Unexpected statuses", &self.unexpected_statuses)?;
+        write_counts(f, "Errors", &self.errors)?;
+        if self.retries > 0{
+            writeln!(f, "Retries -> {} ({} requests retried, {} succeeded after retry)", self.retries, self.retried, self.recovered)?;
+        }
+
+        let Some(overall) = &self.overall else {
+            return Ok(());
+        };
+
+        let mix: Vec<String> = self.per_kind.iter()
+            .map(|(kind, summary)| format!("{kind}={} ({:.1}%)", summary.count, summary.count as f64 * 100.0 / overall.count as f64))
+            .collect();
+        writeln!(f, "Task mix -> {}", mix.join(" "))?;
+        write_table_load(f, &self.per_table, overall.count)?;
+
+        writeln!(f)?;
+        writeln!(f, "{:<8} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+            "latency", "count", "min(ms)", "mean(ms)", "p50(ms)", "p90(ms)", "p99(ms)", "p99.9(ms)", "max(ms)")?;
+        for (kind, summary) in &self.per_kind{
+            write_latency_row(f, kind, summary)?;
+        }
+        write_latency_row(f, &"All", overall)
+    }
+}
+
+
+#[cfg(test)]
+mod test{
+    use std::time::SystemTime;
+
+    use crate::{gen::TaskInfo, status::ExpectedStatuses, test_support};
+
+    use super::*;
+
+    fn millis(values: &[u64]) -> Vec<Duration>{
+        values.iter().map(|x| Duration::from_millis(*x)).collect()
+    }
+
+    #[test]
+    fn test_percentile(){
+        let sorted = millis(&(1..=1000).collect::<Vec<u64>>());
+
+        assert_eq!(percentile(&sorted, 50.0), Duration::from_millis(500));
+        assert_eq!(percentile(&sorted, 90.0), Duration::from_millis(900));
+        assert_eq!(percentile(&sorted, 99.9), Duration::from_millis(999));
+        assert_eq!(percentile(&sorted, 100.0), Duration::from_millis(1000));
+        assert_eq!(percentile(&sorted, 0.0), Duration::from_millis(1));
+    }
+
+    #[test]
+    fn test_latency_summary(){
+        assert!(LatencySummary::from_latencies(&[]).is_none());
+
+        let summary = LatencySummary::from_latencies(&millis(&[30, 10, 20])).unwrap();
+        assert_eq!(summary.count, 3);
+        assert_eq!(summary.min, Duration::from_millis(10));
+        assert_eq!(summary.mean, Duration::from_millis(20));
+        assert_eq!(summary.p50, Duration::from_millis(20));
+        assert_eq!(summary.max, Duration::from_millis(30));
+    }
+
+    #[test]
+    fn test_report_per_kind(){
+        let get_all = test_support::table_task(TaskKind::GetAll, 1, 0);
+        let remove = test_support::table_task(TaskKind::Remove, 2, 1);
+        let result = |task: &TaskInfo, latency: u64, status| TaskResult::new(task, SystemTime::now(), Duration::from_millis(latency), status, Ok("{}".to_string()))
+            .check_status(&ExpectedStatuses::default());
+
+        let results = vec![
+            result(&get_all, 10, Some(200)),
+            result(&get_all, 30, Some(500)),
+            result(&remove, 5, Some(404)),
+            TaskResult::new(&remove, SystemTime::now(), Duration::from_millis(5), None, Err(ClientError::InvalidConfig("test".to_string()))),
+        ];
+
+        let report = LoadReport::from_results(&results);
+        assert_eq!(report.success, 2);
+        assert_eq!(report.failed, 2);
+        assert_eq!(report.per_kind.len(), 2);
+        assert_eq!(report.per_kind[0].0, TaskKind::GetAll);
+        assert_eq!(report.per_kind[0].1.mean, Duration::from_millis(20));
+        assert_eq!(report.per_kind[1].0, TaskKind::Remove);
+        assert_eq!(report.overall.as_ref().unwrap().count, 4);
+        assert_eq!(report.expected_statuses, BTreeMap::from([(404, 1)]));
+        assert_eq!(report.unexpected_statuses, BTreeMap::from([(500, 1)]));
+        assert_eq!(report.errors, BTreeMap::from([(ErrorCategory::InvalidConfig, 1)]));
+        assert_eq!(report.per_table, BTreeMap::from([(1, 2), (2, 2)]));
+        assert!(report.to_string().contains("Table load -> 1=2 (50.0%) 2=2 (50.0%)"));
+        assert_eq!(report.retries, 0);
+        assert!(!report.to_string().contains("Retries"));
+    }
+
+    #[test]
+    fn test_report_retries(){
+        let result = |attempts, status| {
+            let mut result = test_support::result(TaskKind::GetAll, status).check_status(&ExpectedStatuses::default());
+            result.attempts = attempts;
+            result
+        };
+
+        let results = vec![result(1, 200), result(3, 200), result(2, 503)];
+        let report = LoadReport::from_results(&results);
+        assert_eq!((report.retries, report.retried, report.recovered), (3, 2, 1));
+        assert!(report.to_string().contains("Retries -> 3 (2 requests retried, 1 succeeded after retry)"));
+    }
+
+    #[test]
+    fn test_parse_report_output(){
+        assert_eq!("json=out/report.json".parse::<ReportOutput>().unwrap(), ReportOutput::Json(PathBuf::from("out/report.json")));
+        assert!("json=".parse::<ReportOutput>().is_err());
+        assert!("csv=report.csv".parse::<ReportOutput>().is_err());
+    }
+
+    #[test]
+    fn test_write_json(){
+        let config = test_support::config();
+        let get_one = test_support::task(TaskKind::GetOne);
+        let results = vec![
+            TaskResult::new(&get_one, SystemTime::now(), Duration::from_millis(10), Some(200), Ok("{}".to_string())),
+            TaskResult::new(&get_one, SystemTime::now(), Duration::from_millis(30), Some(404), Ok("{}".to_string())),
+            TaskResult::new(&get_one, SystemTime::now(), Duration::from_millis(5), None, Err(ClientError::DeadlineExceeded)),
+        ];
+        let started_at = SystemTime::now();
+        let run = RunInfo { seed: 42, engine: "async".to_string(), started_at, finished_at: started_at + Duration::from_secs(2), config };
+
+        let path = std::env::temp_dir().join(format!("report-test-{}.json", std::process::id()));
+        LoadReport::from_results(&results).write_json(&run, &path).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(json["schema_version"], REPORT_SCHEMA_VERSION);
+        assert_eq!(json["run"]["seed"], "42");
+        assert!(json["run"]["config"].is_object());
+        assert_eq!(json["totals"]["requests"], 3);
+        assert_eq!(json["totals"]["failed"], 1);
+        assert_eq!(json["totals"]["throughput_per_sec"], 1.5);
+        assert_eq!(json["latency_ms"]["max"], 30.0);
+        assert_eq!(json["per_kind"][0]["kind"], "GET_ONE");
+        assert_eq!(json["per_kind"][0]["statuses"], serde_json::json!({"200": 1, "404": 1}));
+        assert_eq!(json["per_kind"][0]["errors"], serde_json::json!({"timeout": 1}));
+        assert_eq!(json["errors"]["timeout"], 1);
+    }
+}
@@ -1,82 +1,163 @@
-use reqwest::{blocking::{Client, Response}, header::CONTENT_TYPE, Error};
-use std::{thread::sleep, time::Duration};
+use reqwest::{blocking::{Client, RequestBuilder, Response}, header::CONTENT_TYPE, Error, Method, Url};
+use std::{thread::sleep, time::{Duration, Instant, SystemTime}};
 
-use crate::{gen::{TaskInfo, TaskKind}, error::ClientError};
+use crate::{config::{Config, HttpVersion}, gen::{TaskInfo, TaskKind}, error::ClientError, observer::TaskObserver, result::TaskResult, retry::{self, RetryPolicy}, status::ExpectedStatuses};
 use rayon::prelude::*;
 
-/// Function that converts response from reqwest to string. Then, put current thread to sleep for delay
-pub fn response_to_text_with_delay( (response, delay_time) : (Response, u64)) -> Result<String, Error>{
-    let result = response.text()?;
-    sleep(Duration::from_secs(delay_time));
-    Ok(result)
+/// This function builds HTTP client from config. The client keeps a connection pool, so it should be built once and shared by all requests
+pub fn build_client(config: &Config) -> Result<Client, ClientError>{
+    let options = config.get_client_options()?;
+    let builder = Client::builder()
+            .pool_max_idle_per_host(options.pool_max_idle_per_host)
+            .pool_idle_timeout(options.pool_idle_timeout)
+            .connect_timeout(options.connect_timeout)
+            .timeout(options.request_timeout);
+
+    let builder = match options.http_version{
+        HttpVersion::Auto => builder,
+        HttpVersion::Http1 => builder.http1_only(),
+        HttpVersion::Http2 => builder.http2_prior_knowledge(),
+    };
+    builder.build().map_err(|err| ClientError::BuilderError(Box::new(err)))
+}
+
+/// Function that converts response from reqwest to string
+pub fn response_to_text(response: Response) -> Result<String, Error>{
+    response.text()
 }
 
 
-/// This function send add orders request to application server
-pub fn request_add_orders(task: &TaskInfo) -> Result<String, ClientError>{
-    let json = task.get_table_order_request_json_string().map_err(|_| ClientError::RequestError)?;
-    tracing::info!("[request add orders] {}", task.endpoint_url);  
-    
-    // tracing::info!("{endpoint}");
-    Client::new()
-            .post(&task.endpoint_url)
-            .header(CONTENT_TYPE, "application/json")
-            .body(json)
-            .send()
-            .and_then(|res| Ok((res, task.delay_time)))
-            .and_then(response_to_text_with_delay)
-            .map_err(|_|ClientError::RequestError)
+/// This function sends the prepared request and records status, latency and response of the task
+fn send_request(task: &TaskInfo, request: RequestBuilder) -> TaskResult{
+    let started_at = SystemTime::now();
+    let start = Instant::now();
 
+    let response = request.send();
+    let status = response.as_ref().ok().map(|res| res.status().as_u16());
+    let retry_after = response.as_ref().ok().and_then(|res| retry::retry_after(res.headers()));
+    let outcome = response
+            .and_then(response_to_text)
+            .map_err(ClientError::from);
+
+    let mut result = TaskResult::new(task, started_at, start.elapsed(), status, outcome);
+    result.retry_after = retry_after;
+    result
 }
 
-/// This function send get all orders (for a table) request to application server
-pub fn request_get_all_orders(task: &TaskInfo) -> Result<String, ClientError>{
-    tracing::info!("[request get all order] {}", task.endpoint_url);  
-    
-    Client::new()
-            .get(&task.endpoint_url)
-            .send()
-            .and_then(|res| Ok((res, task.delay_time)))
-            .and_then(response_to_text_with_delay)
-            .map_err(|_|ClientError::RequestError)
 
+/// This function returns method, url and body (add orders request carries the orders as json) of the task's request.
+/// Each engine builds its own request from them
+pub fn task_request(task: &TaskInfo) -> Result<(Method, Url, Option<String>), ClientError>{
+    let url = Url::parse(&task.endpoint_url).map_err(|err| ClientError::BuilderError(Box::new(err)))?;
+    let request = match task.kind{
+        TaskKind::Add => (Method::POST, url, Some(task.get_table_order_request_json_string()?)),
+        TaskKind::GetAll | TaskKind::GetOne => (Method::GET, url, None),
+        TaskKind::Remove => (Method::DELETE, url, None),
+    };
+    Ok(request)
 }
 
-/// This function send get one specific order request to application server
-pub fn request_get_one_order(task: &TaskInfo) -> Result<String, ClientError>{
-    tracing::info!("[request get one order] {}", task.endpoint_url);  
+/// This function converts task into HTTP request
+pub fn build_request(client: &Client, task: &TaskInfo) -> Result<RequestBuilder, ClientError>{
+    let (method, url, body) = task_request(task)?;
+    let request = client.request(method, url);
+    Ok(match body{
+        Some(body) => request.header(CONTENT_TYPE, "application/json").body(body),
+        None => request
+    })
+}
 
-    Client::new()
-            .get(&task.endpoint_url)
-            .send()
-            .and_then(|res| Ok((res, task.delay_time)))
-            .and_then(response_to_text_with_delay)
-            .map_err(|_|ClientError::RequestError)
+/// This function sends the request of the task. `timeout` replaces the client request timeout for this request only
+fn request_task(client: &Client, task: &TaskInfo, timeout: Option<Duration>) -> TaskResult{
+    let request = match build_request(client, task){
+        Ok(request) => request,
+        Err(err) => return TaskResult::new(task, SystemTime::now(), Duration::ZERO, None, Err(err))
+    };
+    tracing::info!("[request {}] {}", task.kind, task.endpoint_url);
 
+    match timeout{
+        Some(timeout) => send_request(task, request.timeout(timeout)),
+        None => send_request(task, request)
+    }
 }
 
-/// This function send remove order request to application server
-pub fn request_remove_order(task: &TaskInfo) -> Result<String, ClientError>{    
-    tracing::info!("[request remove order] {}", task.endpoint_url);   
-    
-    Client::new()
-            .delete(&task.endpoint_url)
-            .send()
-            .and_then(|res| Ok((res, task.delay_time)))
-            .and_then(response_to_text_with_delay)
-            .map_err(|_|ClientError::RequestError)
 
+/// This function sends the request of the task only if the task is of the expected kind (otherwise, it fails without sending a request)
+fn request_task_of_kind(client: &Client, task: &TaskInfo, kind: TaskKind) -> TaskResult{
+    if task.kind != kind{
+        let err = ClientError::InvalidConfig(format!("{} task cannot be sent as {} request", task.kind, kind));
+        return TaskResult::new(task, SystemTime::now(), Duration::ZERO, None, Err(err));
+    }
+    request_task(client, task, None)
 }
 
+/// This function send add orders request to application server
+pub fn request_add_orders(client: &Client, task: &TaskInfo) -> TaskResult{
+    request_task_of_kind(client, task, TaskKind::Add)
+}
 
-/// A wrapper function for load test
-pub fn execute_tasks(tasks: &[TaskInfo]) -> Vec<Result<String, ClientError>>{
-    let results: Vec<Result<String, ClientError>> = tasks.par_iter().map(|task| match task.kind{
-        TaskKind::Add => request_add_orders(task),
-        TaskKind::GetAll => request_get_all_orders(task),
-        TaskKind::GetOne => request_get_one_order(task),
-        TaskKind::Remove => request_remove_order(task),
-    }).collect();
+/// This function send get all orders (for a table) request to application server
+pub fn request_get_all_orders(client: &Client, task: &TaskInfo) -> TaskResult{
+    request_task_of_kind(client, task, TaskKind::GetAll)
+}
 
-    results
-}
\ No newline at end of file
+/// This function send get one specific order request to application server
+pub fn request_get_one_order(client: &Client, task: &TaskInfo) -> TaskResult{
+    request_task_of_kind(client, task, TaskKind::GetOne)
+}
+
+/// This function send remove order request to application server
+pub fn request_remove_order(client: &Client, task: &TaskInfo) -> TaskResult{
+    request_task_of_kind(client, task, TaskKind::Remove)
+}
+
+
+/// This function executes a single task and checks the response status. Failed requests are retried according to the retry policy
+/// (the latency then covers all attempts). Then, it puts current thread to sleep for the task's delay time.
+/// Each attempt is limited by the request timeout of the retry policy. The request is cancelled at `deadline` of the load test or when
+/// the total timeout of the task runs out (reported as timed out), and it is not retried after that
+pub fn execute_task(client: &Client, task: &TaskInfo, expected: &ExpectedStatuses, retry: &RetryPolicy, deadline: Option<Instant>, observer: &dyn TaskObserver) -> TaskResult{
+    observer.on_task_start(task);
+    let started_at = SystemTime::now();
+    let start = Instant::now();
+    let task_deadline = retry.task_deadline(start, deadline);
+    let remaining = |deadline: Option<Instant>| deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+    let mut attempt = 1;
+    let mut result = loop{
+        let result = match retry.attempt_timeout(task_deadline){
+            Duration::ZERO => TaskResult::new(task, SystemTime::now(), Duration::ZERO, None, Err(ClientError::DeadlineExceeded)),
+            timeout => request_task(client, task, Some(timeout)).check_status(expected)
+        };
+
+        if !retry.should_retry(task.kind, &result, attempt){
+            break result;
+        }
+        let backoff = retry.backoff(attempt, result.retry_after, &mut rand::thread_rng());
+        if remaining(task_deadline).is_some_and(|remaining| remaining <= backoff){
+            break result;
+        }
+        sleep(backoff);
+        attempt += 1;
+    };
+    if attempt > 1{
+        result.attempts = attempt;
+        result.started_at = started_at;
+        result.latency = start.elapsed();
+    }
+    observer.on_task_complete(task, &result);
+
+    let delay = Duration::from_secs(task.delay_time);
+    sleep(remaining(deadline).map_or(delay, |remaining| remaining.min(delay)));
+    result
+}
+
+
+/// A wrapper function for load test. The client is shared by all workers and tasks are taken from the iterator only when a worker is free.
+/// No task is started after `deadline`
+pub fn execute_tasks(client: &Client, tasks: impl Iterator<Item = TaskInfo> + Send, expected: &ExpectedStatuses, retry: &RetryPolicy, deadline: Option<Instant>, observer: &dyn TaskObserver) -> Vec<TaskResult>{
+    tasks.take_while(|_| deadline.map(|deadline| Instant::now() < deadline).unwrap_or(true))
+        .par_bridge()
+        .map(|task| execute_task(client, &task, expected, retry, deadline, observer))
+        .collect()
+}
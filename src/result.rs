@@ -0,0 +1,92 @@
+use std::time::{Duration, SystemTime};
+
+use restaurant_server::model::TableOrdersResponse;
+
+use crate::{error::{ClientError, ErrorCategory}, gen::{TaskInfo, TaskKind}, status::ExpectedStatuses};
+
+
+/// Result record of one executed task. It keeps the task parameters and what happened during the request
+#[derive(Debug)]
+pub struct TaskResult{
+    /// task category
+    pub kind: TaskKind,
+
+    /// target table id
+    pub table_id: i16,
+
+    /// target order id (for get one order and remove order function)
+    pub order_id: Option<i32>,
+
+    /// HTTP status code (`None` if no response was received)
+    pub status: Option<u16>,
+
+    /// time spent on the request itself (the artificial delay time is excluded)
+    pub latency: Duration,
+
+    /// size of the response body in bytes
+    pub bytes: usize,
+
+    /// time when the request was started
+    pub started_at: SystemTime,
+
+    /// response text or error
+    pub outcome: Result<String, ClientError>,
+
+    /// number of attempts of the request (more than `1` if it was retried)
+    pub attempts: u32,
+
+    /// waiting time requested by the server (`Retry-After` header)
+    pub retry_after: Option<Duration>
+}
+
+impl TaskResult{
+    /// init function to build result of the task. The response size is derived from the outcome
+    pub fn new(task: &TaskInfo, started_at: SystemTime, latency: Duration, status: Option<u16>, outcome: Result<String, ClientError>) -> Self{
+        let bytes = outcome.as_ref().map(|body| body.len()).unwrap_or(0);
+        Self { kind: task.kind, table_id: task.table_id, order_id: task.order_id, status, latency, bytes, started_at, outcome, attempts: 1, retry_after: None }
+    }
+
+    /// function turns a received response into HTTP error if its status is not 2xx and not expected for the task kind
+    pub fn check_status(mut self, expected: &ExpectedStatuses) -> Self{
+        if let (Ok(body), Some(status)) = (&self.outcome, self.status){
+            if !expected.is_expected(self.kind, status){
+                self.outcome = Err(ClientError::from_status(status, body));
+            }
+        }
+        self
+    }
+
+    /// utility function to check whether the request was retried
+    pub fn is_retried(&self) -> bool{
+        self.attempts > 1
+    }
+
+    /// utility function to check whether the task succeeded
+    pub fn is_success(&self) -> bool{
+        self.outcome.is_ok()
+    }
+
+    /// utility function to check whether the task succeeded with an expected non-2xx status (e.g., 404 for random order id)
+    pub fn is_expected_non_2xx(&self) -> bool{
+        self.is_success() && self.status.is_some_and(|status| !(200..300).contains(&status))
+    }
+
+    /// utility function to get error category (if the task failed)
+    pub fn error_category(&self) -> Option<ErrorCategory>{
+        self.outcome.as_ref().err().map(ClientError::category)
+    }
+
+    /// function decodes 2xx response body into table orders response (the response of add orders, get all orders and get one order).
+    /// `None` is returned if no 2xx response was received
+    pub fn table_orders(&self) -> Option<serde_json::Result<TableOrdersResponse>>{
+        match (self.status, &self.outcome){
+            (Some(status), Ok(body)) if (200..300).contains(&status) => Some(serde_json::from_str(body)),
+            _ => None
+        }
+    }
+
+    /// utility function to convert into plain response text / error result
+    pub fn into_result(self) -> Result<String, ClientError>{
+        self.outcome
+    }
+}
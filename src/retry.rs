@@ -0,0 +1,236 @@
+use std::{str::FromStr, time::{Duration, Instant}};
+
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+
+use crate::{error::{ClientError, ErrorCategory}, gen::TaskKind, result::TaskResult};
+
+
+/// Failures which are retried: error categories (e.g., `connect`) and HTTP statuses (e.g., `503`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryOn{
+    categories: Vec<ErrorCategory>,
+    statuses: Vec<u16>
+}
+
+impl RetryOn{
+    /// function checks whether the failed result should be retried. HTTP errors are matched by status, other errors by category
+    fn matches(&self, result: &TaskResult) -> bool{
+        match (result.error_category(), result.status){
+            (Some(ErrorCategory::Http4xx | ErrorCategory::Http5xx | ErrorCategory::HttpOther), Some(status)) => self.statuses.contains(&status),
+            (Some(category), _) => self.categories.contains(&category),
+            (None, _) => false
+        }
+    }
+}
+
+impl Default for RetryOn{
+    /// transport failures and statuses which mean "try again later"
+    fn default() -> Self {
+        Self {
+            categories: vec![ErrorCategory::Connect, ErrorCategory::Dns, ErrorCategory::Timeout, ErrorCategory::Request],
+            statuses: vec![429, 502, 503, 504]
+        }
+    }
+}
+
+impl FromStr for RetryOn{
+    type Err = ClientError;
+
+    /// parse comma separated error categories and statuses from config, e.g. `connect,timeout,503`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut retry_on = Self { categories: vec![], statuses: vec![] };
+
+        for entry in s.split(',').map(str::trim).filter(|entry| !entry.is_empty()){
+            if let Ok(status) = entry.parse::<u16>(){
+                if !(100..600).contains(&status){
+                    return Err(ClientError::InvalidConfig(format!("invalid HTTP status `{entry}` in retry conditions")));
+                }
+                retry_on.statuses.push(status);
+            }else{
+                retry_on.categories.push(entry.parse()?);
+            }
+        }
+        Ok(retry_on)
+    }
+}
+
+
+/// Retry policy of failed requests. The backoff grows exponentially from `base_backoff` up to `max_backoff`.
+/// Each attempt is limited by `request_timeout` and all attempts of the task by `total_timeout`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy{
+    /// maximum number of attempts (including the first one). `1` disables retries
+    pub max_attempts: u32,
+
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+
+    /// fraction (0.0 - 1.0) of the backoff which is randomized. `1.0` is full jitter, `0.0` is no jitter
+    pub jitter: f64,
+
+    pub retry_on: RetryOn,
+
+    /// retry add orders (not idempotent) even if the server may have processed the request.
+    /// Otherwise, add orders is only retried when the request was not sent (connect / DNS errors)
+    pub retry_non_idempotent: bool,
+
+    /// timeout of each attempt (the same as the client timeout). It is needed because a shorter per-request timeout replaces the client timeout
+    pub request_timeout: Duration,
+
+    /// time budget of the task including all attempts and backoffs. The attempt in progress is cancelled when it runs out
+    pub total_timeout: Option<Duration>
+}
+
+impl RetryPolicy{
+    /// policy without retries
+    pub fn none() -> Self{
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    /// function checks whether the task should be attempted again after `attempt` attempts
+    pub fn should_retry(&self, kind: TaskKind, result: &TaskResult, attempt: u32) -> bool{
+        if attempt >= self.max_attempts || !self.retry_on.matches(result){
+            return false;
+        }
+        let not_sent = matches!(result.error_category(), Some(ErrorCategory::Connect | ErrorCategory::Dns));
+        kind != TaskKind::Add || self.retry_non_idempotent || not_sent
+    }
+
+    /// function returns when the task runs out of time: at the load test `deadline` or when `total_timeout` from `start` is spent (whichever is earlier)
+    pub fn task_deadline(&self, start: Instant, deadline: Option<Instant>) -> Option<Instant>{
+        match (self.total_timeout.map(|timeout| start + timeout), deadline){
+            (Some(budget), Some(deadline)) => Some(budget.min(deadline)),
+            (budget, deadline) => budget.or(deadline)
+        }
+    }
+
+    /// function returns timeout of the next attempt: the request timeout, but not longer than the time left until `deadline`
+    pub fn attempt_timeout(&self, deadline: Option<Instant>) -> Duration{
+        deadline.map_or(self.request_timeout, |deadline| deadline.saturating_duration_since(Instant::now()).min(self.request_timeout))
+    }
+
+    /// function returns waiting time before the next attempt. `Retry-After` of the server is honoured (up to `max_backoff`)
+    pub fn backoff(&self, attempt: u32, retry_after: Option<Duration>, rng: &mut impl Rng) -> Duration{
+        let exponential = self.base_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff);
+        let backoff = exponential.mul_f64(1.0 - self.jitter * rng.gen::<f64>());
+
+        match retry_after{
+            Some(retry_after) => backoff.max(retry_after.min(self.max_backoff)),
+            None => backoff
+        }
+    }
+}
+
+impl Default for RetryPolicy{
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            base_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            jitter: 1.0,
+            retry_on: RetryOn::default(),
+            retry_non_idempotent: false,
+            request_timeout: Duration::from_secs(30),
+            total_timeout: None
+        }
+    }
+}
+
+
+/// utility function to get `Retry-After` (in seconds) of the response
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration>{
+    headers.get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .map(Duration::from_secs)
+}
+
+
+#[cfg(test)]
+mod test{
+    use std::time::SystemTime;
+
+    use crate::test_support;
+
+    use super::*;
+
+    fn failed(kind: TaskKind, status: Option<u16>, err: ClientError) -> TaskResult{
+        TaskResult::new(&test_support::task(kind), SystemTime::now(), Duration::ZERO, status, Err(err))
+    }
+
+    fn connect_error() -> ClientError{
+        // nothing should listen on port 1
+        ClientError::from(reqwest::blocking::Client::new().get("http://127.0.0.1:1/").send().unwrap_err())
+    }
+
+    #[test]
+    fn test_parse_retry_on(){
+        let retry_on: RetryOn = "connect, Timeout,503".parse().unwrap();
+        assert_eq!(retry_on, RetryOn { categories: vec![ErrorCategory::Connect, ErrorCategory::Timeout], statuses: vec![503] });
+        assert!("connect,explode".parse::<RetryOn>().is_err());
+        assert!("999".parse::<RetryOn>().is_err());
+    }
+
+    #[test]
+    fn test_should_retry(){
+        let policy = RetryPolicy { max_attempts: 3, ..RetryPolicy::default() };
+        let unavailable = failed(TaskKind::GetOne, Some(503), ClientError::from_status(503, ""));
+
+        assert!(policy.should_retry(TaskKind::GetOne, &unavailable, 1));
+        assert!(policy.should_retry(TaskKind::GetOne, &unavailable, 2));
+        assert!(!policy.should_retry(TaskKind::GetOne, &unavailable, 3));
+        assert!(!RetryPolicy::none().should_retry(TaskKind::GetOne, &unavailable, 1));
+
+        // 404 / 500 are not retried by default
+        assert!(!policy.should_retry(TaskKind::GetOne, &failed(TaskKind::GetOne, Some(500), ClientError::from_status(500, "")), 1));
+        assert!(!policy.should_retry(TaskKind::GetOne, &failed(TaskKind::GetOne, Some(404), ClientError::from_status(404, "")), 1));
+    }
+
+    #[test]
+    fn test_add_orders_is_not_blindly_retried(){
+        let policy = RetryPolicy { max_attempts: 3, ..RetryPolicy::default() };
+        let unavailable = failed(TaskKind::Add, Some(503), ClientError::from_status(503, ""));
+        let not_sent = failed(TaskKind::Add, None, connect_error());
+
+        assert!(!policy.should_retry(TaskKind::Add, &unavailable, 1));
+        assert!(policy.should_retry(TaskKind::Add, &not_sent, 1));
+
+        let policy = RetryPolicy { retry_non_idempotent: true, ..policy };
+        assert!(policy.should_retry(TaskKind::Add, &unavailable, 1));
+    }
+
+    #[test]
+    fn test_task_deadline(){
+        let start = Instant::now();
+        let policy = RetryPolicy { request_timeout: Duration::from_secs(30), total_timeout: Some(Duration::from_secs(2)), ..RetryPolicy::default() };
+
+        assert_eq!(policy.task_deadline(start, None), Some(start + Duration::from_secs(2)));
+        assert_eq!(policy.task_deadline(start, Some(start + Duration::from_secs(1))), Some(start + Duration::from_secs(1)));
+        assert_eq!(policy.task_deadline(start, Some(start + Duration::from_secs(60))), Some(start + Duration::from_secs(2)));
+        assert_eq!(RetryPolicy::none().task_deadline(start, None), None);
+
+        // the request timeout still applies when the deadline is far away
+        assert_eq!(policy.attempt_timeout(None), Duration::from_secs(30));
+        assert_eq!(policy.attempt_timeout(Some(Instant::now() + Duration::from_secs(600))), Duration::from_secs(30));
+        assert!(policy.attempt_timeout(Some(Instant::now() + Duration::from_secs(1))) <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_backoff(){
+        let mut rng = test_support::rng();
+        let policy = RetryPolicy { max_attempts: 5, base_backoff: Duration::from_millis(100), max_backoff: Duration::from_millis(300), jitter: 0.0, ..RetryPolicy::default() };
+
+        assert_eq!(policy.backoff(1, None, &mut rng), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2, None, &mut rng), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3, None, &mut rng), Duration::from_millis(300));
+        assert_eq!(policy.backoff(30, None, &mut rng), Duration::from_millis(300));
+        assert_eq!(policy.backoff(1, Some(Duration::from_millis(250)), &mut rng), Duration::from_millis(250));
+        assert_eq!(policy.backoff(1, Some(Duration::from_secs(10)), &mut rng), Duration::from_millis(300));
+
+        let policy = RetryPolicy { jitter: 1.0, ..policy };
+        assert!((0..100).all(|_| policy.backoff(2, None, &mut rng) <= Duration::from_millis(200)));
+    }
+}
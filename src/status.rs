@@ -0,0 +1,88 @@
+use std::{collections::HashMap, str::FromStr};
+
+use crate::{error::ClientError, gen::TaskKind};
+
+
+/// Non-2xx HTTP statuses which are acceptable for each task kind (2xx statuses are always acceptable)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedStatuses(HashMap<TaskKind, Vec<u16>>);
+
+impl ExpectedStatuses{
+    /// init function without any acceptable non-2xx status
+    pub fn none() -> Self{
+        Self(HashMap::new())
+    }
+
+    /// function checks whether status is acceptable for the task kind
+    pub fn is_expected(&self, kind: TaskKind, status: u16) -> bool{
+        (200..300).contains(&status) || self.0.get(&kind).is_some_and(|statuses| statuses.contains(&status))
+    }
+}
+
+impl Default for ExpectedStatuses{
+    /// by default, 404 is acceptable for get one order and remove order tasks because their order ids are random
+    fn default() -> Self {
+        Self(HashMap::from([
+            (TaskKind::GetOne, vec![404]),
+            (TaskKind::Remove, vec![404]),
+        ]))
+    }
+}
+
+impl FromStr for ExpectedStatuses{
+    type Err = ClientError;
+
+    /// parse statuses from config string, e.g. `GET_ONE=404,REMOVE=404/410`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut statuses: HashMap<TaskKind, Vec<u16>> = HashMap::new();
+
+        for entry in s.split(',').map(str::trim).filter(|entry| !entry.is_empty()){
+            let (kind, codes) = entry.split_once('=')
+                .ok_or_else(|| ClientError::InvalidConfig(format!("expected status entry `{entry}` is not in KIND=STATUS form")))?;
+            let kind: TaskKind = kind.parse()?;
+
+            for code in codes.split('/'){
+                let code = code.trim().parse::<u16>()
+                    .ok()
+                    .filter(|code| (100..600).contains(code))
+                    .ok_or_else(|| ClientError::InvalidConfig(format!("invalid HTTP status `{code}` in `{entry}`")))?;
+                statuses.entry(kind).or_default().push(code);
+            }
+        }
+
+        Ok(Self(statuses))
+    }
+}
+
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_default_expected_statuses(){
+        let expected = ExpectedStatuses::default();
+
+        assert!(expected.is_expected(TaskKind::Add, 201));
+        assert!(!expected.is_expected(TaskKind::Add, 404));
+        assert!(expected.is_expected(TaskKind::GetOne, 404));
+        assert!(expected.is_expected(TaskKind::Remove, 404));
+        assert!(!expected.is_expected(TaskKind::GetAll, 500));
+    }
+
+    #[test]
+    fn test_parse_expected_statuses(){
+        let expected: ExpectedStatuses = "GET_ALL=404/410, remove=500".parse().unwrap();
+
+        assert!(expected.is_expected(TaskKind::GetAll, 404));
+        assert!(expected.is_expected(TaskKind::GetAll, 410));
+        assert!(expected.is_expected(TaskKind::Remove, 500));
+        assert!(!expected.is_expected(TaskKind::GetOne, 404));
+
+        assert_eq!("".parse::<ExpectedStatuses>().unwrap(), ExpectedStatuses::none());
+        assert!("GET_ALL".parse::<ExpectedStatuses>().is_err());
+        assert!("GET_ALL=abc".parse::<ExpectedStatuses>().is_err());
+        assert!("GET_ALL=999".parse::<ExpectedStatuses>().is_err());
+        assert!("UPDATE=404".parse::<ExpectedStatuses>().is_err());
+    }
+}
@@ -0,0 +1,207 @@
+use std::str::FromStr;
+
+use rand::Rng;
+
+use crate::error::ClientError;
+
+
+/// How target tables of generated tasks are distributed over `1..=MAX_TABLES`
+#[derive(Debug, Clone, PartialEq)]
+pub enum TableDistribution{
+    /// every table is equally likely
+    Uniform,
+
+    /// table `k` is chosen with probability proportional to `1 / k^exponent` (table 1 is the hottest)
+    Zipf(f64),
+
+    /// `tables` hot tables (tables 1..=tables) receive `share` (0.0 - 1.0) of the traffic, the rest is spread uniformly
+    Hotspot{ tables: i16, share: f64 },
+
+    /// tables are chosen in order 1, 2, ..., MAX_TABLES, 1, 2, ...
+    RoundRobin
+}
+
+impl FromStr for TableDistribution{
+    type Err = ClientError;
+
+    /// parse distribution from config: `uniform`, `zipf[:EXPONENT]` (default exponent 1.0), `hotspot:TABLES:PERCENT` or `round-robin`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ClientError::InvalidConfig(format!("invalid table distribution `{s}` (expected uniform, zipf[:EXPONENT], hotspot:TABLES:PERCENT or round-robin)"));
+
+        let lowercase = s.trim().to_lowercase();
+        let mut parts = lowercase.split(':').map(str::trim);
+        let distribution = match (parts.next(), parts.next(), parts.next()){
+            (Some("uniform"), None, None) => TableDistribution::Uniform,
+            (Some("zipf" | "zipfian"), exponent, None) => {
+                let exponent = exponent.map_or(Ok(1.0), str::parse::<f64>).map_err(|_| invalid())?;
+                if !(exponent.is_finite() && exponent > 0.0){
+                    return Err(invalid());
+                }
+                TableDistribution::Zipf(exponent)
+            },
+            (Some("hotspot"), Some(tables), Some(percent)) => {
+                let tables: i16 = tables.parse().ok().filter(|tables| *tables > 0).ok_or_else(invalid)?;
+                let percent: f64 = percent.trim_end_matches('%').parse().ok().filter(|percent| (0.0..=100.0).contains(percent)).ok_or_else(invalid)?;
+                TableDistribution::Hotspot { tables, share: percent / 100.0 }
+            },
+            (Some("round-robin" | "round_robin" | "roundrobin"), None, None) => TableDistribution::RoundRobin,
+            _ => return Err(invalid())
+        };
+
+        if parts.next().is_some(){
+            return Err(invalid());
+        }
+        Ok(distribution)
+    }
+}
+
+
+/// Sampler of table ids following the table distribution
+#[derive(Debug, Clone)]
+pub struct TableSelector{
+    max_tables: i16,
+    sampler: Sampler
+}
+
+#[derive(Debug, Clone)]
+enum Sampler{
+    Uniform,
+
+    /// cumulative probability of tables 1..=max_tables
+    Cumulative(Vec<f64>),
+
+    /// next table id
+    RoundRobin(i16)
+}
+
+impl TableSelector{
+    pub fn new(distribution: &TableDistribution, max_tables: i16) -> Self{
+        let max_tables = max_tables.max(1);
+        let sampler = match distribution{
+            TableDistribution::Uniform => Sampler::Uniform,
+            TableDistribution::Zipf(exponent) => Sampler::Cumulative(cumulative((1..=max_tables).map(|table_id| 1.0 / (table_id as f64).powf(*exponent)))),
+            TableDistribution::Hotspot { tables, share } => {
+                let hot_tables = (*tables).min(max_tables);
+                let cold_tables = max_tables - hot_tables;
+                // without cold tables, hot tables receive all traffic
+                let share = if cold_tables == 0 { 1.0 } else { *share };
+                Sampler::Cumulative(cumulative((1..=max_tables).map(|table_id| {
+                    if table_id <= hot_tables { share / hot_tables as f64 } else { (1.0 - share) / cold_tables as f64 }
+                })))
+            },
+            TableDistribution::RoundRobin => Sampler::RoundRobin(1),
+        };
+        Self { max_tables, sampler }
+    }
+
+    /// function selects table id of the next task
+    pub fn select(&mut self, rng: &mut impl Rng) -> i16{
+        match &mut self.sampler{
+            Sampler::Uniform => rng.gen_range(1..=self.max_tables),
+            Sampler::Cumulative(cdf) => {
+                let point: f64 = rng.gen();
+                let index = cdf.partition_point(|probability| *probability <= point);
+                (index.min(cdf.len() - 1) + 1) as i16
+            },
+            Sampler::RoundRobin(next) => {
+                let table_id = *next;
+                *next = if table_id >= self.max_tables { 1 } else { table_id + 1 };
+                table_id
+            }
+        }
+    }
+}
+
+/// function converts weights of tables 1..=N into cumulative probabilities
+fn cumulative(weights: impl Iterator<Item = f64>) -> Vec<f64>{
+    let weights: Vec<f64> = weights.collect();
+    let total: f64 = weights.iter().sum();
+
+    weights.iter()
+        .scan(0.0, |sum, weight| {
+            *sum += weight / total;
+            Some(*sum)
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod test{
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn counts(distribution: &TableDistribution, max_tables: i16, samples: usize) -> Vec<usize>{
+        let mut selector = TableSelector::new(distribution, max_tables);
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut counts = vec![0; max_tables as usize];
+        for _ in 0..samples{
+            counts[selector.select(&mut rng) as usize - 1] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn test_parse_distribution(){
+        assert_eq!("Uniform".parse::<TableDistribution>().unwrap(), TableDistribution::Uniform);
+        assert_eq!("zipf".parse::<TableDistribution>().unwrap(), TableDistribution::Zipf(1.0));
+        assert_eq!("zipf:1.5".parse::<TableDistribution>().unwrap(), TableDistribution::Zipf(1.5));
+        assert_eq!("hotspot:3:90%".parse::<TableDistribution>().unwrap(), TableDistribution::Hotspot { tables: 3, share: 0.9 });
+        assert_eq!("round-robin".parse::<TableDistribution>().unwrap(), TableDistribution::RoundRobin);
+        assert!("normal".parse::<TableDistribution>().is_err());
+        assert!("zipf:0".parse::<TableDistribution>().is_err());
+        assert!("zipf:1:2".parse::<TableDistribution>().is_err());
+        assert!("hotspot:3".parse::<TableDistribution>().is_err());
+        assert!("hotspot:0:50".parse::<TableDistribution>().is_err());
+        assert!("hotspot:3:150".parse::<TableDistribution>().is_err());
+    }
+
+    #[test]
+    fn test_round_robin(){
+        let mut selector = TableSelector::new(&TableDistribution::RoundRobin, 3);
+        let mut rng = StdRng::seed_from_u64(1);
+        let tables: Vec<i16> = (0..7).map(|_| selector.select(&mut rng)).collect();
+        assert_eq!(tables, vec![1, 2, 3, 1, 2, 3, 1]);
+    }
+
+    #[test]
+    fn test_uniform_covers_all_tables(){
+        let counts = counts(&TableDistribution::Uniform, 10, 10000);
+        assert!(counts.iter().all(|count| (800..1200).contains(count)));
+    }
+
+    #[test]
+    fn test_zipf_prefers_low_tables(){
+        let counts = counts(&TableDistribution::Zipf(1.0), 10, 10000);
+
+        // P(table 1) = 1 / H(10) ~ 34%, P(table 10) ~ 3.4%
+        assert!((3000..3800).contains(&counts[0]));
+        assert!(counts[0] > counts[1] && counts[1] > counts[4] && counts[4] > counts[9]);
+        assert!(counts[9] > 0);
+    }
+
+    #[test]
+    fn test_zipf_exponent_increases_skew(){
+        let mild = counts(&TableDistribution::Zipf(0.5), 10, 10000);
+        let steep = counts(&TableDistribution::Zipf(2.0), 10, 10000);
+
+        // P(table 1) with exponent 2.0 ~ 65%
+        assert!(steep[0] > mild[0]);
+        assert!((6000..7000).contains(&steep[0]));
+    }
+
+    #[test]
+    fn test_hotspot_share(){
+        let hotspot = counts(&TableDistribution::Hotspot { tables: 2, share: 0.8 }, 10, 10000);
+
+        let hot: usize = hotspot[..2].iter().sum();
+        assert!((7700..8300).contains(&hot));
+        assert!(hotspot[2..].iter().all(|count| (150..350).contains(count)));
+
+        // hot tables beyond MAX_TABLES receive all traffic
+        let all_hot = counts(&TableDistribution::Hotspot { tables: 20, share: 0.5 }, 4, 1000);
+        assert_eq!(all_hot.iter().sum::<usize>(), 1000);
+        assert!(all_hot.iter().all(|count| *count > 0));
+    }
+}
@@ -0,0 +1,62 @@
+use std::time::{Duration, SystemTime};
+
+use rand::{rngs::StdRng, SeedableRng};
+use restaurant_server::model::TableOrdersRequest;
+
+use crate::{config::Config, gen::{TaskInfo, TaskKind}, result::TaskResult};
+
+
+/// seed of the random generator of test fixtures
+const SEED: u64 = 42;
+
+/// variables of test config (the same values as `.env` of the repository)
+const CONFIG: [(&str, &str); 6] = [
+    ("ENDPOINT", "http://localhost:3333"),
+    ("MAX_THREADS", "16"),
+    ("MAX_REQUEST_TASKS", "100"),
+    ("MAX_TABLES", "10"),
+    ("MAX_ORDERS_PER_REQUEST", "4"),
+    ("MAX_ORDER_ID", "10"),
+];
+
+
+/// config of unit tests. It does not depend on environment variables
+pub(crate) fn config() -> Config{
+    config_with(&[])
+}
+
+/// test config with additional variables (e.g. `TASK_WEIGHTS`)
+pub(crate) fn config_with(vars: &[(&str, &str)]) -> Config{
+    let vars = CONFIG.iter().chain(vars).map(|(name, value)| (name.to_string(), value.to_string()));
+    envy::from_iter(vars).expect("invalid test config")
+}
+
+/// seeded random generator, so the fixtures are the same in every run
+pub(crate) fn rng() -> StdRng{
+    StdRng::seed_from_u64(SEED)
+}
+
+/// task of the kind for table 1 (and order 1)
+pub(crate) fn task(kind: TaskKind) -> TaskInfo{
+    table_task(kind, 1, 1)
+}
+
+/// task of the kind for the table. `order_id` is used by get one order and remove order tasks, and add orders task adds one order
+pub(crate) fn table_task(kind: TaskKind, table_id: i16, order_id: i32) -> TaskInfo{
+    let config = config();
+    match kind{
+        TaskKind::Add => {
+            let mut orders = TableOrdersRequest::new(table_id);
+            orders.add_order_wihtout_note("item-1");
+            TaskInfo::new_add_task(table_id, orders, &config, &mut rng())
+        },
+        TaskKind::GetAll => TaskInfo::new_get_all_task(table_id, &config, &mut rng()),
+        TaskKind::GetOne => TaskInfo::new_get_one_task(table_id, order_id, &config, &mut rng()),
+        TaskKind::Remove => TaskInfo::new_remove_task(table_id, order_id, &config, &mut rng()),
+    }
+}
+
+/// result of the task of the kind which received response with the status (and `{}` body) in 10 milliseconds
+pub(crate) fn result(kind: TaskKind, status: u16) -> TaskResult{
+    TaskResult::new(&task(kind), SystemTime::now(), Duration::from_millis(10), Some(status), Ok("{}".to_string()))
+}
@@ -0,0 +1,171 @@
+use std::{fmt, fs::File, io::{BufWriter, Write}, path::Path, time::{Duration, SystemTime}};
+
+use crate::{error::ClientError, report::LatencySummary, result::TaskResult};
+
+
+/// shortest interval of time series (all intervals until the last completed request are kept)
+const MIN_INTERVAL: Duration = Duration::from_millis(1);
+
+
+/// Requests completed in one interval of the load test
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interval{
+    /// start of the interval (from the beginning of the load test)
+    pub start: Duration,
+
+    pub requests: usize,
+    pub errors: usize,
+
+    /// latency of the requests completed in the interval (`None` if there is no request)
+    pub latency: Option<LatencySummary>
+}
+
+/// Time series of completed requests, errors and latency in fixed intervals
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeSeries{
+    pub interval: Duration,
+
+    /// all intervals from the beginning of the load test until the last completed request (empty intervals included)
+    pub intervals: Vec<Interval>
+}
+
+impl TimeSeries{
+    /// function buckets results by their completion time (from `start`) into intervals of the given length
+    pub fn from_results(results: &[TaskResult], start: SystemTime, interval: Duration) -> Result<Self, ClientError>{
+        if interval < MIN_INTERVAL{
+            return Err(ClientError::InvalidConfig(format!("time series interval must be at least {MIN_INTERVAL:?} (got {interval:?})")));
+        }
+
+        let too_long = || ClientError::InvalidConfig(format!("time series interval {interval:?} is too short for the duration of the load test"));
+        let mut buckets: Vec<(usize, Vec<Duration>)> = vec![];
+        for result in results{
+            let completed = (result.started_at + result.latency).duration_since(start).unwrap_or_default();
+            let index = u32::try_from(completed.as_nanos() / interval.as_nanos()).map_err(|_| too_long())? as usize;
+            if index >= buckets.len(){
+                buckets.resize_with(index + 1, Default::default);
+            }
+            let (errors, latencies) = &mut buckets[index];
+            latencies.push(result.latency);
+            *errors += usize::from(!result.is_success());
+        }
+
+        let intervals = buckets.into_iter()
+            .enumerate()
+            .map(|(index, (errors, latencies))| Ok(Interval {
+                // the index fits into u32 (checked above)
+                start: interval.checked_mul(index as u32).ok_or_else(too_long)?,
+                requests: latencies.len(),
+                errors,
+                latency: LatencySummary::from_latencies(&latencies)
+            }))
+            .collect::<Result<_, ClientError>>()?;
+        Ok(Self { interval, intervals })
+    }
+
+    /// function writes the time series as CSV (latency columns are empty for intervals without requests)
+    pub fn write_csv(&self, path: impl AsRef<Path>) -> Result<(), ClientError>{
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "start_s,end_s,requests,errors,rps,p50_ms,p90_ms,p99_ms,max_ms")?;
+        for interval in &self.intervals{
+            let latency = match &interval.latency{
+                Some(s) => format!("{:.3},{:.3},{:.3},{:.3}", as_millis(s.p50), as_millis(s.p90), as_millis(s.p99), as_millis(s.max)),
+                None => ",,,".to_string()
+            };
+            writeln!(writer, "{:.3},{:.3},{},{},{:.1},{latency}",
+                interval.start.as_secs_f64(), (interval.start + self.interval).as_secs_f64(), interval.requests, interval.errors, self.rate(interval))?;
+        }
+        Ok(writer.flush()?)
+    }
+
+    /// requests per second completed in the interval
+    fn rate(&self, interval: &Interval) -> f64{
+        interval.requests as f64 / self.interval.as_secs_f64()
+    }
+}
+
+fn as_millis(duration: Duration) -> f64{
+    duration.as_secs_f64() * 1000.0
+}
+
+impl fmt::Display for TimeSeries{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:>8} {:>8} {:>8} {:>8} {:>10} {:>10} {:>10}", "time(s)", "requests", "rps", "errors", "p50(ms)", "p99(ms)", "max(ms)")?;
+        for interval in &self.intervals{
+            write!(f, "{:>8.1} {:>8} {:>8.1} {:>8}", interval.start.as_secs_f64(), interval.requests, self.rate(interval), interval.errors)?;
+            match &interval.latency{
+                Some(s) => writeln!(f, " {:>10.3} {:>10.3} {:>10.3}", as_millis(s.p50), as_millis(s.p99), as_millis(s.max))?,
+                None => writeln!(f, " {:>10} {:>10} {:>10}", "-", "-", "-")?
+            }
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod test{
+    use crate::{gen::TaskKind, test_support};
+
+    use super::*;
+
+    fn results(start: SystemTime) -> Vec<TaskResult>{
+        let task = test_support::task(TaskKind::GetAll);
+        // (start offset, latency) in milliseconds
+        [(0, 100), (200, 300), (900, 50), (2500, 1000)].into_iter()
+            .map(|(offset, latency)| {
+                let outcome = if latency >= 1000 { Err(ClientError::DeadlineExceeded) } else { Ok("{}".to_string()) };
+                TaskResult::new(&task, start + Duration::from_millis(offset), Duration::from_millis(latency), Some(200), outcome)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_time_series(){
+        let start = SystemTime::now();
+        let series = TimeSeries::from_results(&results(start), start, Duration::from_secs(1)).unwrap();
+
+        // completed at 100 ms, 500 ms, 950 ms and 3500 ms
+        let requests: Vec<usize> = series.intervals.iter().map(|interval| interval.requests).collect();
+        assert_eq!(requests, vec![3, 0, 0, 1]);
+        assert_eq!(series.intervals[0].latency.as_ref().unwrap().max, Duration::from_millis(300));
+        assert!(series.intervals[1].latency.is_none());
+        assert_eq!(series.intervals[3].errors, 1);
+        assert_eq!(series.intervals[3].start, Duration::from_secs(3));
+
+        let text = series.to_string();
+        assert!(text.contains("     1.0        0      0.0        0          -          -          -"), "{text}");
+
+        let series = TimeSeries::from_results(&results(start), start, Duration::from_millis(500)).unwrap();
+        assert_eq!(series.intervals.len(), 8);
+        assert_eq!(series.intervals[0].requests, 1);
+        assert!(TimeSeries::from_results(&[], start, Duration::ZERO).is_err());
+        assert!(TimeSeries::from_results(&[], start, Duration::from_micros(999)).is_err());
+
+        let series = TimeSeries::from_results(&results(start), start, Duration::from_millis(1)).unwrap();
+        assert_eq!(series.intervals.len(), 3501);
+        assert_eq!(series.intervals[3500].start, Duration::from_millis(3500));
+
+        // more intervals than u32::MAX are rejected (before they are allocated)
+        let mut late = results(start);
+        late[0].started_at = start + Duration::from_secs(5_000_000);
+        assert!(TimeSeries::from_results(&late, start, Duration::from_millis(1)).is_err());
+    }
+
+    #[test]
+    fn test_write_csv(){
+        let start = SystemTime::now();
+        let series = TimeSeries::from_results(&results(start), start, Duration::from_secs(1)).unwrap();
+
+        let path = std::env::temp_dir().join(format!("timeseries-test-{}.csv", std::process::id()));
+        series.write_csv(&path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "start_s,end_s,requests,errors,rps,p50_ms,p90_ms,p99_ms,max_ms");
+        assert_eq!(lines[1], "0.000,1.000,3,0,3.0,100.000,300.000,300.000,300.000");
+        assert_eq!(lines[2], "1.000,2.000,0,0,0.0,,,,");
+        assert_eq!(lines[4], "3.000,4.000,1,1,1.0,1000.000,1000.000,1000.000,1000.000");
+    }
+}
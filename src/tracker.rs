@@ -0,0 +1,92 @@
+use std::sync::Mutex;
+
+use rand::Rng;
+use restaurant_server::model::TableOrdersResponse;
+
+use crate::{gen::{TaskInfo, TaskKind}, observer::TaskObserver, result::TaskResult};
+
+
+/// Orders created by add orders tasks during the load test. Used by the stateful generator so that
+/// get one order and remove order tasks target orders which really exist
+#[derive(Debug, Default)]
+pub struct OrderTracker{
+    /// (table id, order id) of known orders
+    orders: Mutex<Vec<(i16, i32)>>
+}
+
+impl OrderTracker{
+    /// number of known orders
+    pub fn len(&self) -> usize{
+        self.orders.lock().expect("order tracker is poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.len() == 0
+    }
+
+    /// function returns random known order (the order is kept)
+    pub fn pick(&self, rng: &mut impl Rng) -> Option<(i16, i32)>{
+        let orders = self.orders.lock().expect("order tracker is poisoned");
+        (!orders.is_empty()).then(|| orders[rng.gen_range(0..orders.len())])
+    }
+
+    /// function removes random known order and returns it (so it will not be removed twice)
+    pub fn take(&self, rng: &mut impl Rng) -> Option<(i16, i32)>{
+        let mut orders = self.orders.lock().expect("order tracker is poisoned");
+        (!orders.is_empty()).then(|| {
+            let index = rng.gen_range(0..orders.len());
+            orders.swap_remove(index)
+        })
+    }
+
+    /// function records orders in the response of add orders request
+    fn record_added_orders(&self, response: &TableOrdersResponse){
+        let added = response.orders.iter().map(|order| (response.table_id, order.order_id));
+        self.orders.lock().expect("order tracker is poisoned").extend(added);
+    }
+}
+
+impl TaskObserver for OrderTracker{
+    fn on_task_complete(&self, _task: &TaskInfo, result: &TaskResult){
+        if result.kind != TaskKind::Add{
+            return;
+        }
+        match result.table_orders(){
+            Some(Ok(response)) => self.record_added_orders(&response),
+            Some(Err(err)) => tracing::warn!("cannot parse add orders response: {err}"),
+            None => {}
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test{
+    use std::time::{Duration, SystemTime};
+
+    use crate::{gen::TaskKind, test_support};
+
+    use super::*;
+
+    #[test]
+    fn test_track_added_orders(){
+        let task = test_support::table_task(TaskKind::Add, 3, 0);
+        let body = r#"{"table_id":3,"orders":[{"order_id":7,"table_id":3,"item_name":"item-1","note":null},{"order_id":8,"table_id":3,"item_name":"item-2","note":null}]}"#;
+
+        let tracker = OrderTracker::default();
+        tracker.on_task_complete(&task, &TaskResult::new(&task, SystemTime::now(), Duration::ZERO, Some(500), Ok(body.to_string())));
+        assert!(tracker.is_empty());
+
+        tracker.on_task_complete(&task, &TaskResult::new(&task, SystemTime::now(), Duration::ZERO, Some(201), Ok(body.to_string())));
+        assert_eq!(tracker.len(), 2);
+
+        let mut rng = test_support::rng();
+        assert_eq!(tracker.pick(&mut rng).unwrap().0, 3);
+        assert_eq!(tracker.len(), 2);
+
+        let first = tracker.take(&mut rng).unwrap();
+        let second = tracker.take(&mut rng).unwrap();
+        assert_ne!(first, second);
+        assert!(tracker.take(&mut rng).is_none());
+    }
+}
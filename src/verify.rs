@@ -0,0 +1,308 @@
+use std::{collections::{BTreeMap, HashMap}, fmt, sync::Mutex, time::SystemTime};
+
+use restaurant_server::model::TableOrdersResponse;
+
+use crate::{gen::{TaskInfo, TaskKind}, observer::TaskObserver, result::TaskResult};
+
+
+/// Invariant of the server response which is violated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ViolationKind{
+    /// the response body is not a table orders response
+    InvalidBody,
+
+    /// the response (or an order in it) belongs to another table
+    WrongTable,
+
+    /// add orders response does not contain all submitted items
+    MissingItems,
+
+    /// get one order response does not contain exactly the requested order
+    WrongOrder,
+
+    /// get one order returned an order which had been removed before the request was sent
+    RemovedOrderReturned
+}
+
+impl fmt::Display for ViolationKind{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self{
+            ViolationKind::InvalidBody => "invalid_body",
+            ViolationKind::WrongTable => "wrong_table",
+            ViolationKind::MissingItems => "missing_items",
+            ViolationKind::WrongOrder => "wrong_order",
+            ViolationKind::RemovedOrderReturned => "removed_order_returned",
+        };
+        f.pad(name)
+    }
+}
+
+
+/// One incorrect response found by the verifier
+#[derive(Debug, Clone)]
+pub struct Violation{
+    pub kind: ViolationKind,
+
+    /// kind of the task which received the response
+    pub task: TaskKind,
+
+    pub table_id: i16,
+    pub order_id: Option<i32>,
+
+    /// human readable description
+    pub detail: String
+}
+
+impl fmt::Display for Violation{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {} table {}", self.kind, self.task, self.table_id)?;
+        if let Some(order_id) = self.order_id{
+            write!(f, " order {order_id}")?;
+        }
+        write!(f, ": {}", self.detail)
+    }
+}
+
+
+#[derive(Debug, Default)]
+struct VerifierState{
+    /// number of verified responses
+    checked: usize,
+
+    violations: Vec<Violation>,
+
+    /// completion time of succeeded remove order tasks for each (table id, order id)
+    removed: HashMap<(i16, i32), SystemTime>
+}
+
+/// Observer which checks that 2xx responses are correct for their task kind:
+/// add orders returns the same table and all submitted items, get one order returns exactly the requested order,
+/// get all orders only returns orders of the table and a removed order is not returned by get one order anymore
+#[derive(Debug, Default)]
+pub struct ResponseVerifier{
+    state: Mutex<VerifierState>
+}
+
+impl ResponseVerifier{
+    /// function summarizes violations found so far
+    pub fn report(&self) -> VerificationReport{
+        let state = self.state.lock().expect("response verifier is poisoned");
+
+        let mut counts = BTreeMap::new();
+        for violation in &state.violations{
+            *counts.entry(violation.kind).or_insert(0) += 1;
+        }
+        VerificationReport {
+            checked: state.checked,
+            violations: counts,
+            examples: state.violations.iter().take(REPORTED_VIOLATIONS).cloned().collect()
+        }
+    }
+
+    /// function checks the response of the task. Tasks without 2xx response are skipped
+    fn verify(&self, task: &TaskInfo, result: &TaskResult){
+        let Some(response) = result.table_orders() else {
+            return;
+        };
+
+        let mut state = self.state.lock().expect("response verifier is poisoned");
+        state.checked += 1;
+
+        let violation = |kind: ViolationKind, detail: String| Violation { kind, task: task.kind, table_id: task.table_id, order_id: task.order_id, detail };
+        if task.kind == TaskKind::Remove{
+            if let Some(order_id) = task.order_id{
+                state.removed.insert((task.table_id, order_id), result.started_at + result.latency);
+            }
+            return;
+        }
+
+        let response = match response{
+            Ok(response) => response,
+            Err(err) => {
+                state.violations.push(violation(ViolationKind::InvalidBody, err.to_string()));
+                return;
+            }
+        };
+
+        let checked = check_table(&response, task.table_id).and_then(|_| match task.kind{
+            TaskKind::Add => check_added_items(&response, task),
+            TaskKind::GetOne => check_one_order(&response, task.order_id, result.started_at, &state.removed),
+            TaskKind::GetAll | TaskKind::Remove => Ok(())
+        });
+        if let Err((kind, detail)) = checked{
+            state.violations.push(violation(kind, detail));
+        }
+
+        // order ids created by add orders are not removed anymore
+        if task.kind == TaskKind::Add{
+            for order in &response.orders{
+                state.removed.remove(&(response.table_id, order.order_id));
+            }
+        }
+    }
+}
+
+impl TaskObserver for ResponseVerifier{
+    fn on_task_complete(&self, task: &TaskInfo, result: &TaskResult){
+        self.verify(task, result)
+    }
+}
+
+
+type Check = Result<(), (ViolationKind, String)>;
+
+/// function checks that the response and all its orders belong to the table
+fn check_table(response: &TableOrdersResponse, table_id: i16) -> Check{
+    if response.table_id != table_id{
+        return Err((ViolationKind::WrongTable, format!("response is for table {}", response.table_id)));
+    }
+    match response.orders.iter().find(|order| order.table_id != table_id){
+        Some(order) => Err((ViolationKind::WrongTable, format!("order {} belongs to table {}", order.order_id, order.table_id))),
+        None => Ok(())
+    }
+}
+
+/// function checks that the add orders response contains every submitted item
+fn check_added_items(response: &TableOrdersResponse, task: &TaskInfo) -> Check{
+    let submitted = task.table_orders_request.as_ref().map(|request| request.orders.as_slice()).unwrap_or_default();
+    if response.orders.len() < submitted.len(){
+        return Err((ViolationKind::MissingItems, format!("{} orders submitted, {} returned", submitted.len(), response.orders.len())));
+    }
+
+    let mut returned: HashMap<&str, usize> = HashMap::new();
+    for order in &response.orders{
+        *returned.entry(order.item_name.as_str()).or_insert(0) += 1;
+    }
+    for item in submitted.iter().map(|order| order.item_name.as_str()){
+        match returned.get_mut(item){
+            Some(count) if *count > 0 => *count -= 1,
+            _ => return Err((ViolationKind::MissingItems, format!("item `{item}` is not returned")))
+        }
+    }
+    Ok(())
+}
+
+/// function checks that get one order response contains exactly the requested order, which was not removed before the request
+fn check_one_order(response: &TableOrdersResponse, order_id: Option<i32>, started_at: SystemTime, removed: &HashMap<(i16, i32), SystemTime>) -> Check{
+    let Some(order_id) = order_id else {
+        return Ok(());
+    };
+    let returned: Vec<i32> = response.orders.iter().map(|order| order.order_id).collect();
+    if returned != [order_id]{
+        return Err((ViolationKind::WrongOrder, format!("expected exactly order {order_id}, got {returned:?}")));
+    }
+    match removed.get(&(response.table_id, order_id)){
+        Some(removed_at) if *removed_at <= started_at => Err((ViolationKind::RemovedOrderReturned, "order was removed before the request".to_string())),
+        _ => Ok(())
+    }
+}
+
+
+/// number of violations shown in the report
+const REPORTED_VIOLATIONS: usize = 5;
+
+/// Summary of response verification
+#[derive(Debug)]
+pub struct VerificationReport{
+    /// number of verified (2xx) responses
+    pub checked: usize,
+
+    /// number of violations for each kind
+    pub violations: BTreeMap<ViolationKind, usize>,
+
+    /// first violations
+    pub examples: Vec<Violation>
+}
+
+impl fmt::Display for VerificationReport{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total: usize = self.violations.values().sum();
+        writeln!(f, "Verification -> CHECKED:{} / VIOLATIONS: {total}", self.checked)?;
+        if total == 0{
+            return Ok(());
+        }
+
+        let counts: Vec<String> = self.violations.iter().map(|(kind, count)| format!("{kind}={count}")).collect();
+        writeln!(f, "Violations -> {}", counts.join(" "))?;
+        for violation in &self.examples{
+            writeln!(f, "  {violation}")?;
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod test{
+    use std::time::Duration;
+
+    use restaurant_server::model::TableOrdersRequest;
+    use serde_json::json;
+
+    use crate::test_support;
+
+    use super::*;
+
+    /// table orders response with orders given as (order id, table id, item name)
+    fn response(table_id: i16, orders: &[(i32, i16, &str)]) -> String{
+        let orders: Vec<_> = orders.iter()
+            .map(|(order_id, table_id, item_name)| json!({ "order_id": order_id, "table_id": table_id, "item_name": item_name, "note": null }))
+            .collect();
+        json!({ "table_id": table_id, "orders": orders }).to_string()
+    }
+
+    fn complete(verifier: &ResponseVerifier, task: &TaskInfo, started_at: SystemTime, status: u16, body: &str){
+        verifier.on_task_complete(task, &TaskResult::new(task, started_at, Duration::from_millis(1), Some(status), Ok(body.to_string())));
+    }
+
+    #[test]
+    fn test_verify_add_and_get(){
+        let verifier = ResponseVerifier::default();
+        let now = SystemTime::now();
+
+        let mut orders = TableOrdersRequest::new(2);
+        orders.add_order_wihtout_note("item-2-0");
+        orders.add_order_wihtout_note("item-2-1");
+        let add = TaskInfo::new_add_task(2, orders, &test_support::config(), &mut test_support::rng());
+        complete(&verifier, &add, now, 201, &response(2, &[(1, 2, "item-2-0"), (2, 2, "item-2-1")]));
+        complete(&verifier, &add, now, 201, &response(2, &[(3, 2, "item-2-0"), (4, 2, "item-2-0")]));
+
+        let get_one = test_support::table_task(TaskKind::GetOne, 2, 1);
+        complete(&verifier, &get_one, now, 200, &response(2, &[(1, 2, "item-2-0")]));
+        complete(&verifier, &get_one, now, 200, &response(2, &[(1, 2, "item-2-0"), (2, 2, "item-2-1")]));
+        // 404 and errors are not verified
+        complete(&verifier, &get_one, now, 404, "not found");
+
+        let get_all = test_support::table_task(TaskKind::GetAll, 2, 0);
+        complete(&verifier, &get_all, now, 200, &response(2, &[(1, 2, "item-2-0"), (9, 3, "item-3-0")]));
+        complete(&verifier, &get_all, now, 200, "internal error");
+
+        let report = verifier.report();
+        assert_eq!(report.checked, 6);
+        assert_eq!(report.violations, BTreeMap::from([
+            (ViolationKind::InvalidBody, 1),
+            (ViolationKind::WrongTable, 1),
+            (ViolationKind::MissingItems, 1),
+            (ViolationKind::WrongOrder, 1)
+        ]));
+        assert!(report.to_string().contains("VIOLATIONS: 4"));
+    }
+
+    #[test]
+    fn test_removed_order_returned(){
+        let verifier = ResponseVerifier::default();
+        let now = SystemTime::now();
+
+        let remove = test_support::table_task(TaskKind::Remove, 1, 5);
+        let get_one = test_support::table_task(TaskKind::GetOne, 1, 5);
+        let body = response(1, &[(5, 1, "item-1-0")]);
+
+        complete(&verifier, &remove, now, 200, "");
+        // started concurrently with the removal
+        complete(&verifier, &get_one, now, 200, &body);
+        assert!(verifier.report().violations.is_empty());
+
+        complete(&verifier, &get_one, now + Duration::from_secs(1), 200, &body);
+        assert_eq!(verifier.report().violations, BTreeMap::from([(ViolationKind::RemovedOrderReturned, 1)]));
+    }
+}
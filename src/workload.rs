@@ -0,0 +1,190 @@
+use std::{fs::File, io::{BufRead, BufReader, BufWriter, Write}, path::Path, sync::{Mutex, OnceLock}, time::{Duration, Instant}};
+
+use restaurant_server::model::TableOrdersRequest;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Config, error::ClientError, gen::{TaskInfo, TaskKind, TASK_SCHEMA_VERSION}, observer::TaskObserver};
+
+
+/// One line of recorded workload file (JSON lines), e.g. `{"version":1,"offset_ms":12,"kind":"GET_ALL","table_id":3,"delay_time":0}`.
+/// Fields of the task follow the schema of `TaskInfo`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RecordedTask{
+    /// schema version of the record (`TASK_SCHEMA_VERSION`). Files recorded before the schema was versioned have no version (same as version 1)
+    #[serde(default)]
+    pub version: Option<u32>,
+
+    /// start time of the task (milliseconds from the beginning of the load test)
+    pub offset_ms: u64,
+
+    pub kind: TaskKind,
+
+    pub table_id: i16,
+
+    #[serde(default)]
+    pub order_id: Option<i32>,
+
+    /// orders of add orders task
+    #[serde(default)]
+    pub orders: Option<TableOrdersRequest>,
+
+    #[serde(default)]
+    pub delay_time: u64
+}
+
+impl RecordedTask{
+    /// function checks schema version and parameters of the recorded task. Then, the task is built with endpoint url from config
+    pub fn into_task(self, config: &Config) -> Result<TaskInfo, ClientError>{
+        let version = self.version.unwrap_or(1);
+        if version != TASK_SCHEMA_VERSION{
+            return Err(ClientError::InvalidConfig(format!("unsupported task schema version {version} (expected {TASK_SCHEMA_VERSION})")));
+        }
+        TaskInfo::from_fields(self.kind, self.table_id, self.order_id, self.orders, self.delay_time, config)
+    }
+}
+
+/// Recorded task which is written to the file (same fields as `RecordedTask`)
+#[derive(Serialize)]
+struct RecordedTaskRef<'a>{
+    version: u32,
+    offset_ms: u64,
+
+    #[serde(flatten)]
+    task: &'a TaskInfo
+}
+
+
+/// Observer which records every started task into workload file, so the same workload can be replayed later
+pub struct WorkloadRecorder{
+    /// start of the load test (when the first task is started, so setup before the load test is not recorded as delay)
+    start: OnceLock<Instant>,
+    writer: Mutex<BufWriter<File>>
+}
+
+impl WorkloadRecorder{
+    /// init function to create (or truncate) workload file
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, ClientError>{
+        let file = File::create(path)?;
+        Ok(Self { start: OnceLock::new(), writer: Mutex::new(BufWriter::new(file)) })
+    }
+
+    /// function writes buffered records to the file. Should be called when the load test is completed
+    pub fn finish(&self) -> Result<(), ClientError>{
+        Ok(self.writer.lock().expect("workload recorder is poisoned").flush()?)
+    }
+
+    fn record(&self, task: &TaskInfo) -> Result<(), ClientError>{
+        let offset = self.start.get_or_init(Instant::now).elapsed();
+        let offset_ms = u64::try_from(offset.as_millis()).unwrap_or(u64::MAX);
+        let record = RecordedTaskRef { version: TASK_SCHEMA_VERSION, offset_ms, task };
+
+        let mut writer = self.writer.lock().expect("workload recorder is poisoned");
+        serde_json::to_writer(&mut *writer, &record).map_err(|err| ClientError::SerializationError(Box::new(err)))?;
+        Ok(writer.write_all(b"\n")?)
+    }
+}
+
+impl Drop for WorkloadRecorder{
+    /// the records are written even if `finish` is not called (e.g., the load test failed)
+    fn drop(&mut self){
+        if let Ok(writer) = self.writer.get_mut(){
+            if let Err(err) = writer.flush(){
+                tracing::warn!("cannot write workload file: {err}");
+            }
+        }
+    }
+}
+
+impl TaskObserver for WorkloadRecorder{
+    fn on_task_start(&self, task: &TaskInfo){
+        if let Err(err) = self.record(task){
+            tracing::warn!("cannot record task: {err}");
+        }
+    }
+}
+
+
+/// function reads recorded workload file. Tasks are returned with their start time, ordered by the start time
+pub fn read_workload(path: impl AsRef<Path>, config: &Config) -> Result<Vec<(Duration, TaskInfo)>, ClientError>{
+    let reader = BufReader::new(File::open(path)?);
+
+    let mut tasks = vec![];
+    for (index, line) in reader.lines().enumerate(){
+        let line = line?;
+        if line.trim().is_empty(){
+            continue;
+        }
+        let record: RecordedTask = serde_json::from_str(&line)
+            .map_err(|err| ClientError::InvalidConfig(format!("invalid workload record at line {}: {err}", index + 1)))?;
+        tasks.push((Duration::from_millis(record.offset_ms), record.into_task(config)?));
+    }
+
+    // tasks are recorded by concurrent workers, so the start times may be slightly out of order
+    tasks.sort_by_key(|(offset, _)| *offset);
+    Ok(tasks)
+}
+
+
+#[cfg(test)]
+mod test{
+    use crate::test_support;
+
+    use super::*;
+
+    #[test]
+    fn test_record_and_read_workload(){
+        let config = test_support::config();
+        let path = std::env::temp_dir().join(format!("workload-test-{}.jsonl", std::process::id()));
+
+        let mut remove = test_support::table_task(TaskKind::Remove, 5, 7);
+        remove.delay_time = 2;
+
+        let recorder = WorkloadRecorder::create(&path).unwrap();
+        // setup before the first task is not recorded as its start time
+        std::thread::sleep(Duration::from_millis(100));
+        recorder.on_task_start(&test_support::table_task(TaskKind::Add, 3, 0));
+        recorder.on_task_start(&remove);
+        recorder.finish().unwrap();
+
+        let tasks = read_workload(&path, &config).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        let (offset, add) = &tasks[0];
+        assert!(*offset < Duration::from_millis(100));
+        assert_eq!(add.kind, TaskKind::Add);
+        assert_eq!(add.table_id, 3);
+        assert_eq!(add.table_orders_request.as_ref().unwrap().orders.len(), 1);
+
+        let (_, remove) = &tasks[1];
+        assert_eq!(remove.kind, TaskKind::Remove);
+        assert_eq!((remove.table_id, remove.order_id, remove.delay_time), (5, Some(7), 2));
+        assert_eq!(remove.endpoint_url, format!("{}/api/v1/tables/5/orders/7", config.get_endpoint()));
+    }
+
+    #[test]
+    fn test_unversioned_record(){
+        // records written before the schema was versioned
+        let config = test_support::config();
+        let record: RecordedTask = serde_json::from_str(r#"{"offset_ms":5,"kind":"REMOVE","table_id":2,"order_id":9,"delay_time":1}"#).unwrap();
+        let task = record.into_task(&config).unwrap();
+        assert_eq!((task.kind, task.table_id, task.order_id, task.delay_time), (TaskKind::Remove, 2, Some(9), 1));
+        assert_eq!(task.endpoint_url, format!("{}/api/v1/tables/2/orders/9", config.get_endpoint()));
+    }
+
+    #[test]
+    fn test_invalid_record(){
+        let config = test_support::config();
+        let parse = |line: &str| serde_json::from_str::<RecordedTask>(line).map_err(|err| ClientError::SerializationError(Box::new(err))).and_then(|record| record.into_task(&config));
+
+        assert!(parse(r#"{"version":1,"offset_ms":0,"kind":"GET_ONE","table_id":1,"order_id":3}"#).is_ok());
+        // missing order id
+        assert!(parse(r#"{"version":1,"offset_ms":0,"kind":"GET_ONE","table_id":1}"#).is_err());
+        // unknown schema version
+        assert!(parse(r#"{"version":2,"offset_ms":0,"kind":"GET_ALL","table_id":1}"#).is_err());
+        // unknown kind and field
+        assert!(parse(r#"{"version":1,"offset_ms":0,"kind":"UPDATE","table_id":1}"#).is_err());
+        assert!(parse(r#"{"version":1,"offset_ms":0,"kind":"GET_ALL","table_id":1,"endpoint_url":"http://localhost"}"#).is_err());
+    }
+}
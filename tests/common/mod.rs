@@ -0,0 +1,13 @@
+use restaurant_client::{config::Config, mock::{MockOptions, MockServer}, request::build_client};
+use reqwest::blocking::Client;
+
+
+/// start mock server and load config which points to the server (the server is stopped when it is dropped)
+pub fn setup(options: MockOptions) -> (MockServer, Config, Client){
+    dotenvy::dotenv().ok();
+    let server = MockServer::start(options).unwrap();
+    let mut config = envy::from_env::<Config>().expect("Cannot load config from env (exit now)");
+    config.set_endpoint(&server.endpoint());
+    let client = build_client(&config).unwrap();
+    (server, config, client)
+}
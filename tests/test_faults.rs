@@ -0,0 +1,138 @@
+use std::time::{Duration, Instant};
+
+use restaurant_client::{config::Config, error::ErrorCategory, gen::TaskInfo, mock::MockOptions, observer::Observers, request::*, retry::RetryPolicy, status::ExpectedStatuses};
+use restaurant_server::model::TableOrdersRequest;
+use reqwest::blocking::Client;
+
+mod common;
+use common::setup;
+
+
+/// mock server options which inject the fault into every request
+fn fault(fault: &str) -> MockOptions{
+    MockOptions { faults: vec![format!("*:{fault}:1").parse().unwrap()], ..Default::default() }
+}
+
+/// error category of get all orders request
+fn get_all_error(config: &Config, client: &Client) -> Option<ErrorCategory>{
+    let task = TaskInfo::new_get_all_task(1, config, &mut rand::thread_rng());
+    request_get_all_orders(client, &task).check_status(&ExpectedStatuses::default()).error_category()
+}
+
+
+#[test]
+fn test_server_error_is_http_5xx(){
+    let (_server, config, client) = setup(fault("server-error"));
+    assert_eq!(get_all_error(&config, &client), Some(ErrorCategory::Http5xx));
+}
+
+#[test]
+fn test_unavailable_is_http_5xx(){
+    let (_server, config, client) = setup(fault("unavailable=3"));
+
+    let task = TaskInfo::new_get_all_task(1, &config, &mut rand::thread_rng());
+    let result = request_get_all_orders(&client, &task).check_status(&ExpectedStatuses::default());
+    assert_eq!(result.status, Some(503));
+    assert_eq!(result.error_category(), Some(ErrorCategory::Http5xx));
+    assert_eq!(result.retry_after, Some(Duration::from_secs(3)));
+}
+
+#[test]
+fn test_unavailable_is_retried(){
+    let (_server, config, client) = setup(fault("unavailable=3"));
+    let retry = RetryPolicy { max_attempts: 3, max_backoff: Duration::from_millis(10), ..RetryPolicy::default() };
+
+    // Retry-After is capped by the maximum backoff
+    let task = TaskInfo::new_get_all_task(1, &config, &mut rand::thread_rng());
+    let result = execute_task(&client, &task, &ExpectedStatuses::default(), &retry, None, &Observers::default());
+    assert_eq!(result.attempts, 3);
+    assert_eq!(result.status, Some(503));
+
+    // the server may have processed add orders, so it is not retried
+    let mut orders = TableOrdersRequest::new(1);
+    orders.add_order_wihtout_note("item-1");
+    let task = TaskInfo::new_add_task(1, orders, &config, &mut rand::thread_rng());
+    let result = execute_task(&client, &task, &ExpectedStatuses::default(), &retry, None, &Observers::default());
+    assert_eq!(result.attempts, 1);
+}
+
+#[test]
+fn test_truncated_body_is_decode_error(){
+    let (_server, config, client) = setup(fault("truncate"));
+    assert_eq!(get_all_error(&config, &client), Some(ErrorCategory::Decode));
+}
+
+/// the mock server closes the connection after it has read the request and before it writes the status line,
+/// so the connection is already established (it is not a connect error) and no response is received
+#[test]
+fn test_connection_close_is_request_error(){
+    let (_server, config, client) = setup(fault("close"));
+    assert_eq!(get_all_error(&config, &client), Some(ErrorCategory::Request));
+}
+
+#[test]
+fn test_slow_response_times_out(){
+    let (_server, mut config, _) = setup(fault("slow=2s"));
+    config.set_request_timeout(Duration::from_millis(200));
+    let client = build_client(&config).unwrap();
+
+    assert_eq!(get_all_error(&config, &client), Some(ErrorCategory::Timeout));
+}
+
+#[test]
+fn test_deadline_cancels_hung_requests(){
+    let (_server, config, client) = setup(fault("slow=5s"));
+    let tasks = (1..=100).map(|table_id| TaskInfo::new_get_all_task(table_id, &config, &mut rand::thread_rng()));
+
+    let start = Instant::now();
+    let deadline = Some(start + Duration::from_millis(300));
+    let results = execute_tasks(&client, tasks, &ExpectedStatuses::default(), &RetryPolicy::none(), deadline, &Observers::default());
+
+    // the requests in flight at the deadline are reported as timed out and the rest are not started
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert!(!results.is_empty() && results.len() < 100);
+    assert!(results.iter().all(|result| result.error_category() == Some(ErrorCategory::Timeout)));
+}
+
+#[test]
+fn test_request_timeout_fires_before_far_deadline(){
+    let (_server, config, client) = setup(fault("slow=2s"));
+    let retry = RetryPolicy { request_timeout: Duration::from_millis(200), ..RetryPolicy::none() };
+
+    // the time left until the deadline must not replace the shorter request timeout
+    let task = TaskInfo::new_get_all_task(1, &config, &mut rand::thread_rng());
+    let start = Instant::now();
+    let deadline = Some(start + Duration::from_secs(60));
+    let result = execute_task(&client, &task, &ExpectedStatuses::default(), &retry, deadline, &Observers::default());
+    assert!(start.elapsed() < Duration::from_millis(1500));
+    assert_eq!(result.error_category(), Some(ErrorCategory::Timeout));
+}
+
+#[test]
+fn test_total_timeout_limits_retries(){
+    let (_server, config, client) = setup(fault("slow=2s"));
+    let retry = RetryPolicy {
+        max_attempts: 3,
+        max_backoff: Duration::from_millis(10),
+        total_timeout: Some(Duration::from_millis(300)),
+        ..RetryPolicy::default()
+    };
+
+    // the attempt in progress is cancelled when the budget of the task runs out and it is not retried
+    let task = TaskInfo::new_get_all_task(1, &config, &mut rand::thread_rng());
+    let start = Instant::now();
+    let result = execute_task(&client, &task, &ExpectedStatuses::default(), &retry, None, &Observers::default());
+    assert!(start.elapsed() < Duration::from_millis(1500));
+    assert!(result.attempts <= 2);
+    assert_eq!(result.error_category(), Some(ErrorCategory::Timeout));
+}
+
+#[test]
+fn test_malformed_json_is_received(){
+    let (_server, config, client) = setup(fault("malformed"));
+
+    // the client does not parse responses, so malformed JSON is only detected by verification
+    let task = TaskInfo::new_get_all_task(1, &config, &mut rand::thread_rng());
+    let body = request_get_all_orders(&client, &task).into_result().unwrap();
+    assert!(serde_json::from_str::<serde_json::Value>(&body).is_err());
+}
@@ -1,23 +1,30 @@
 use restaurant_server::model::TableOrdersRequest;
 use serde_json::{self, Value};
-use restaurant_client::{request::*, gen::TaskInfo, config::Config};
+use restaurant_client::{request::*, gen::TaskInfo, config::Config, mock::MockOptions};
+use reqwest::blocking::Client;
 
+mod common;
+use common::setup;
 
-#[allow(unused)]
-#[test]
-fn test_request_add_orders(){
-    dotenvy::dotenv();
-    let config = envy::from_env::<Config>().expect("Cannot load config from env (exit now)");
-    
+
+/// add two orders into table 1 and return the response
+fn add_orders(config: &Config, client: &Client) -> String{
     let mut order = TableOrdersRequest::new(1);
     order.add_order("item-1", "note1");
     order.add_order("item-2", "note2");
 
-    let task = TaskInfo::new_add_task( 1, order, &config);
-    let results = request_add_orders(&task).unwrap();
+    let task = TaskInfo::new_add_task( 1, order, config, &mut rand::thread_rng());
+    request_add_orders(client, &task).into_result().unwrap()
+}
+
+
+#[test]
+fn test_request_add_orders(){
+    let (_server, config, client) = setup(MockOptions::default());
+    let results = add_orders(&config, &client);
 
     let json: Value = serde_json::from_str(&results).unwrap();
-    
+
     // check whether the result is TableOrderResponse object (table id and orders exist or not)
     // the correctness check is skipped (already have tests in the server)
 
@@ -25,17 +32,18 @@ fn test_request_add_orders(){
     assert_eq!(table_id.as_i64().unwrap(), 1);
 
     let orders = json.pointer("/orders").unwrap();
-    assert!(orders.as_array().unwrap().len() > 0);    
+    assert!(!orders.as_array().unwrap().is_empty());
 }
 
-#[allow(unused)]
 #[test]
 fn test_request_get_all_orders(){
-    dotenvy::dotenv();
-    let config = envy::from_env::<Config>().expect("Cannot load config from env (exit now)");
+    let (_server, config, client) = setup(MockOptions::default());
+
+    // ensure that order does exist in table 1
+    add_orders(&config, &client);
 
-    let task = TaskInfo::new_get_all_task( 1, &config);
-    let results = request_get_all_orders( &task).unwrap();
+    let task = TaskInfo::new_get_all_task( 1, &config, &mut rand::thread_rng());
+    let results = request_get_all_orders(&client, &task).into_result().unwrap();
 
     let json: Value = serde_json::from_str(&results).unwrap();
 
@@ -45,23 +53,20 @@ fn test_request_get_all_orders(){
     assert_eq!(table_id.as_i64().unwrap(), 1);
 
     let orders = json.pointer("/orders").unwrap();
-    assert!(orders.as_array().unwrap().len() > 0);    
-    
+    assert!(!orders.as_array().unwrap().is_empty());
+
 }
 
 #[test]
-#[allow(unused)]
-
 fn test_request_get_one_order(){
-    dotenvy::dotenv();
-    let config = envy::from_env::<Config>().expect("Cannot load config from env (exit now)");
+    let (_server, config, client) = setup(MockOptions::default());
 
     // ensure that order does exist in table 1
-    test_request_add_orders();
+    add_orders(&config, &client);
+
 
-    
-    let task = TaskInfo::new_get_all_task( 1, &config);
-    let results = request_get_all_orders(&task).unwrap();
+    let task = TaskInfo::new_get_all_task( 1, &config, &mut rand::thread_rng());
+    let results = request_get_all_orders(&client, &task).into_result().unwrap();
     let json_value: Value = serde_json::from_str(&results).unwrap();
 
     let existing_order_id = json_value.pointer("/orders/0/order_id")
@@ -69,8 +74,8 @@ fn test_request_get_one_order(){
                                                 .as_i64().unwrap();
 
 
-    let task = TaskInfo::new_get_one_task( 1, existing_order_id as i32, &config);
-    let results = request_get_one_order(&task).unwrap();
+    let task = TaskInfo::new_get_one_task( 1, existing_order_id as i32, &config, &mut rand::thread_rng());
+    let results = request_get_one_order(&client, &task).into_result().unwrap();
 
     let json: Value = serde_json::from_str(&results).unwrap();
 
@@ -80,25 +85,22 @@ fn test_request_get_one_order(){
     assert_eq!(table_id.as_i64().unwrap(), 1);
 
     let orders = json.pointer("/orders").unwrap();
-    assert!(orders.as_array().unwrap().len() == 1);    
-    
-    
+    assert!(orders.as_array().unwrap().len() == 1);
+
+
 }
 
 
 #[test]
-#[allow(unused)]
-
 fn test_request_remove_one_order(){
-    dotenvy::dotenv();
-    let config = envy::from_env::<Config>().expect("Cannot load config from env (exit now)");
+    let (_server, config, client) = setup(MockOptions::default());
 
     // ensure that order does exist in table 1
-    test_request_add_orders();
+    add_orders(&config, &client);
+
 
-    
-    let task = TaskInfo::new_get_all_task( 1, &config);
-    let results = request_get_all_orders( &task).unwrap();
+    let task = TaskInfo::new_get_all_task( 1, &config, &mut rand::thread_rng());
+    let results = request_get_all_orders(&client, &task).into_result().unwrap();
     let json_value: Value = serde_json::from_str(&results).unwrap();
 
     let existing_order_id = json_value.pointer("/orders/0/order_id")
@@ -106,12 +108,46 @@ fn test_request_remove_one_order(){
                                                 .as_i64().unwrap();
 
 
-    let task = TaskInfo::new_remove_task( 1, existing_order_id as i32, &config);
-    request_remove_order( &task);
-                                            
+    let task = TaskInfo::new_remove_task( 1, existing_order_id as i32, &config, &mut rand::thread_rng());
+    let result = request_remove_order(&client, &task);
+    assert_eq!(result.status, Some(200));
+
+
+
+    // the removed order is not found anymore
+    let task = TaskInfo::new_get_one_task( 1, existing_order_id as i32, &config, &mut rand::thread_rng());
+    let result = request_get_one_order(&client, &task);
+    assert_eq!(result.status, Some(404));
+}
+
+#[test]
+fn test_task_request(){
+    let (_server, config, _) = setup(MockOptions::default());
+    let mut order = TableOrdersRequest::new(1);
+    order.add_order_wihtout_note("item-1");
 
+    let task = TaskInfo::new_add_task(1, order, &config, &mut rand::thread_rng());
+    let (method, url, body) = task_request(&task).unwrap();
+    assert_eq!(method, reqwest::Method::POST);
+    assert_eq!(url.as_str(), task.endpoint_url);
+    assert!(body.unwrap().contains("item-1"));
 
-    let task = TaskInfo::new_get_one_task( 1, existing_order_id as i32, &config);
-    let results = request_get_one_order( &task).unwrap();
-    
+    let task = TaskInfo::new_remove_task(1, 7, &config, &mut rand::thread_rng());
+    let (method, url, body) = task_request(&task).unwrap();
+    assert_eq!((method, url.path(), body), (reqwest::Method::DELETE, "/api/v1/tables/1/orders/7", None));
+}
+
+#[test]
+fn test_request_of_wrong_kind(){
+    let (_server, config, client) = setup(MockOptions::default());
+    add_orders(&config, &client);
+
+    // get all orders task is not sent as remove order request
+    let task = TaskInfo::new_get_all_task(1, &config, &mut rand::thread_rng());
+    let result = request_remove_order(&client, &task);
+    assert_eq!(result.status, None);
+    assert_eq!(result.error_category(), Some(restaurant_client::error::ErrorCategory::InvalidConfig));
+
+    let orders: Value = serde_json::from_str(&request_get_all_orders(&client, &task).into_result().unwrap()).unwrap();
+    assert_eq!(orders["orders"].as_array().unwrap().len(), 2);
 }